@@ -3,11 +3,19 @@
 //! Your systems can then request the resources defined here to access the
 //! loaded assets.
 
-use crate::prelude::*;
+use crate::{
+    audio::{music::MusicManifest, sfx::SfxManifest},
+    game::enemy::archetypes::EnemyArchetypes,
+    prelude::*,
+};
 use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt as _, LoadContext},
     // render::texture::{ImageLoaderSettings, ImageSampler},
     utils::HashMap,
 };
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use thiserror::Error;
 
 pub(super) fn plugin(app: &mut App) {
     // app.register_type::<ImageHandles>();
@@ -18,6 +26,9 @@ pub(super) fn plugin(app: &mut App) {
 
     app.register_type::<SfxHandles>();
     app.init_resource::<SfxHandles>();
+
+    app.register_type::<ArchetypeHandles>();
+    app.init_resource::<ArchetypeHandles>();
 }
 
 // #[derive(Resource, Debug, Deref, DerefMut, Reflect)]
@@ -52,28 +63,40 @@ pub(super) fn plugin(app: &mut App) {
 //     }
 // }
 
-/// Stores the handles for background music, aka soundtracks.
+/// Stores the handle for the music manifest, which describes every
+/// soundtrack and loads their audio along with it.
 #[derive(Resource, Debug, Deref, DerefMut, Reflect)]
 #[reflect(Resource)]
-pub struct BgmHandles(HashMap<String, Handle<AudioSource>>);
+pub struct BgmHandles(HashMap<String, Handle<MusicManifest>>);
 
 impl BgmHandles {
-    pub const PATH_CREDITS: &'static str = "audio/bgm/Chase_Me_credits.ogg";
-    pub const PATH_TITLES: &'static str = "audio/bgm/Chase_Me_titles.ogg";
-    pub const PATH_GAMEPLAY1: &'static str = "audio/bgm/Chase_Me_background1.ogg";
-    pub const PATH_GAMEPLAY2: &'static str = "audio/bgm/Chase_Me_background2.ogg";
+    pub const PATH_MUSIC: &'static str = "data/soundtrack.music.ron";
+
+    // Track names in the music manifest.
+    pub const TRACK_CREDITS: &'static str = "credits";
+    pub const TRACK_TITLES: &'static str = "titles";
+    pub const TRACK_GAMEPLAY: &'static str = "gameplay";
+
+    /// Every soundtrack the game plays, checked against the manifest on the
+    /// loading screen.
+    pub const TRACKS: [&'static str; 3] = [
+        Self::TRACK_CREDITS,
+        Self::TRACK_TITLES,
+        Self::TRACK_GAMEPLAY,
+    ];
+
+    /// The music manifest, once it has loaded.
+    pub fn manifest<'a>(&self, manifests: &'a Assets<MusicManifest>) -> Option<&'a MusicManifest> {
+        self.get(Self::PATH_MUSIC)
+            .and_then(|handle| manifests.get(handle))
+    }
 }
 
 impl FromWorld for BgmHandles {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
 
-        let paths = [
-            Self::PATH_CREDITS,
-            Self::PATH_GAMEPLAY1,
-            Self::PATH_GAMEPLAY2,
-            Self::PATH_TITLES,
-        ];
+        let paths = [Self::PATH_MUSIC];
         let map = paths
             .into_iter()
             .map(|path| (path.to_string(), asset_server.load(path)))
@@ -83,55 +106,140 @@ impl FromWorld for BgmHandles {
     }
 }
 
-/// The values stored here are a `Vec<Handle<AudioSource>>` because
-/// a single sound effect can have multiple variations.
+/// Stores the handle for the sound effect manifest, which maps the names
+/// below to audio files and loads them along with it.
 #[derive(Resource, Debug, Deref, DerefMut, Reflect)]
 #[reflect(Resource)]
-pub struct SfxHandles(HashMap<String, Vec<Handle<AudioSource>>>);
+pub struct SfxHandles(HashMap<String, Handle<SfxManifest>>);
 
 impl SfxHandles {
-    pub const PATH_BUTTON_HOVER: &'static str = "audio/sfx/button_hover.ogg";
-    pub const PATH_BUTTON_PRESS: &'static str = "audio/sfx/button_press.ogg";
-    pub const PATH_CRASH: &'static str = "audio/sfx/crash-course.ogg";
-    pub const PATH_COLLIDE: &'static str = "audio/sfx/hadron-impact.ogg";
-    pub const PATH_DREADNAUGHT: &'static str = "audio/sfx/dreadnaught.ogg";
-    pub const PATH_IMPACT: &'static str = "audio/sfx/impact.ogg";
-    pub const PATH_ARRIVAL: &'static str = "audio/sfx/arrival.ogg";
-    pub const PATH_SELECT: &'static str = "audio/sfx/select.ogg";
-    pub const PATH_TAP: &'static str = "audio/sfx/tap.ogg";
+    pub const PATH_SOUNDS: &'static str = "data/sounds.sfx.ron";
+
+    // Sound effect names in the manifest.
+    pub const KEY_BUTTON_HOVER: &'static str = "ui.hover";
+    pub const KEY_BUTTON_PRESS: &'static str = "ui.press";
+    pub const KEY_ENEMY_ARRIVE: &'static str = "enemy.arrive";
+    pub const KEY_ENEMY_EXPIRE: &'static str = "enemy.expire";
+    pub const KEY_ENEMY_DESTROYED: &'static str = "enemy.destroyed";
+    pub const KEY_SHIP_BUMP: &'static str = "ship.bump";
+    pub const KEY_PLAYER_HIT: &'static str = "player.hit";
+    pub const KEY_PLAYER_CRASH: &'static str = "player.crash";
+    pub const KEY_WAVE_START: &'static str = "wave.start";
+    pub const KEY_WAVE_CLEAR: &'static str = "wave.clear";
+
+    /// Every sound effect the game plays, checked against the manifest on the
+    /// loading screen.
+    pub const KEYS: [&'static str; 10] = [
+        Self::KEY_BUTTON_HOVER,
+        Self::KEY_BUTTON_PRESS,
+        Self::KEY_ENEMY_ARRIVE,
+        Self::KEY_ENEMY_EXPIRE,
+        Self::KEY_ENEMY_DESTROYED,
+        Self::KEY_SHIP_BUMP,
+        Self::KEY_PLAYER_HIT,
+        Self::KEY_PLAYER_CRASH,
+        Self::KEY_WAVE_START,
+        Self::KEY_WAVE_CLEAR,
+    ];
+
+    /// The sound effect manifest, once it has loaded.
+    pub fn manifest<'a>(&self, manifests: &'a Assets<SfxManifest>) -> Option<&'a SfxManifest> {
+        self.get(Self::PATH_SOUNDS)
+            .and_then(|handle| manifests.get(handle))
+    }
 }
 
 impl FromWorld for SfxHandles {
     fn from_world(world: &mut World) -> Self {
-        let asset_server = world.get_resource::<AssetServer>().unwrap();
-
-        let paths = [
-            Self::PATH_BUTTON_HOVER,
-            Self::PATH_BUTTON_PRESS,
-            Self::PATH_CRASH,
-            Self::PATH_COLLIDE,
-            Self::PATH_DREADNAUGHT,
-            Self::PATH_IMPACT,
-            Self::PATH_ARRIVAL,
-            Self::PATH_SELECT,
-            Self::PATH_TAP,
-        ];
-        let map: HashMap<_, _> = paths
+        let asset_server = world.resource::<AssetServer>();
+
+        let paths = [Self::PATH_SOUNDS];
+        let map = paths
             .into_iter()
-            .map(|path| (path.to_string(), vec![asset_server.load(path)]))
+            .map(|path| (path.to_string(), asset_server.load(path)))
             .collect();
 
-        // Using string parsing to strip numbered suffixes + `AssetServer::load_folder`
-        // is a good way to load many sound effects at once, but is not supported on
-        // Wasm or Android.
-        // const STEP_VARIATIONS: u32 = 4;
-        // let mut step_sfx = Vec::new();
-        // for i in 1..=STEP_VARIATIONS {
-        //     let file = format!("{key}{i}.ogg", key = Self::PATH_STEP);
-        //     step_sfx.push(asset_server.load(file));
-        // }
-        // map.insert(Self::PATH_STEP.to_string(), step_sfx);
+        Self(map)
+    }
+}
+
+/// Stores the handles for the enemy archetype data files.
+#[derive(Resource, Debug, Deref, DerefMut, Reflect)]
+#[reflect(Resource)]
+pub struct ArchetypeHandles(HashMap<String, Handle<EnemyArchetypes>>);
+
+impl ArchetypeHandles {
+    pub const PATH_ENEMIES: &'static str = "data/enemies.archetypes.ron";
+}
+
+impl FromWorld for ArchetypeHandles {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+
+        let paths = [Self::PATH_ENEMIES];
+        let map = paths
+            .into_iter()
+            .map(|path| (path.to_string(), asset_server.load(path)))
+            .collect();
 
         Self(map)
     }
 }
+
+/// An extension trait for registering assets that are loaded straight from RON files.
+pub trait RonAssetApp {
+    /// Register the asset type `A` and a loader for files with the given extensions,
+    /// e.g. `&["archetypes.ron"]`.
+    fn init_ron_asset<A: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self;
+}
+
+impl RonAssetApp for App {
+    fn init_ron_asset<A: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self {
+        self.init_asset::<A>()
+            .register_asset_loader(RonAssetLoader::<A> {
+                extensions,
+                _asset: PhantomData,
+            })
+    }
+}
+
+/// Loads an asset of type `A` by deserializing a RON file.
+struct RonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    _asset: PhantomData<fn() -> A>,
+}
+
+#[derive(Debug, Error)]
+pub enum RonAssetError {
+    #[error("could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<A, RonAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -1,30 +1,77 @@
+use std::time::Duration;
+
 use bevy::{
     audio::PlaybackMode,
     ecs::{system::RunSystemOnce as _, world::Command},
     prelude::*,
 };
 
+use super::{
+    mix::{AudioChannel, AudioMix, MixedAudio},
+    music::{IntensityLayer, MusicIntensity, MusicManifest},
+};
 use crate::assets::BgmHandles;
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<IsBgm>();
+    app.register_type::<IsBgm>().add_systems(
+        Update,
+        (fade_bgm, follow_intensity, apply_bgm_volume).chain(),
+    );
 }
 
-/// Marker component for the soundtrack entity so we can find it later.
+/// How long [`BgmCommands::play_bgm`] takes to crossfade between soundtracks.
+pub const DEFAULT_CROSSFADE: Duration = Duration::from_millis(1500);
+
+/// How long [`BgmCommands::stop_bgm`] takes to fade the soundtrack out.
+pub const DEFAULT_FADE_OUT: Duration = Duration::from_millis(1000);
+
+/// Marker component for the soundtrack entities so we can find them later.
+/// There is one entity for each stem of the soundtrack.
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 struct IsBgm;
 
+/// The volume of a soundtrack stem before mixing, made of the fade of the
+/// whole track and the level of this stem's layer.
+#[derive(Component, Debug, Clone, Copy)]
+struct BgmVolume {
+    fade: f32,
+    layer: f32,
+}
+
+/// Moves a soundtrack's fade from one level to another. A soundtrack that
+/// fades to silence is despawned when the fade is done.
+#[derive(Component, Debug)]
+struct BgmFade {
+    timer: Timer,
+    from: f32,
+    to: f32,
+}
+
+impl BgmFade {
+    fn new(duration: Duration, from: f32, to: f32) -> Self {
+        BgmFade {
+            timer: Timer::new(duration, TimerMode::Once),
+            from,
+            to,
+        }
+    }
+
+    fn volume(&self) -> f32 {
+        self.from + (self.to - self.from) * self.timer.fraction()
+    }
+}
+
 /// A custom command used to play soundtracks.
 #[derive(Debug)]
 enum PlayBgm {
-    Key(String),
-    Disable,
+    Key { key: String, fade: Duration },
+    Disable { fade: Duration },
 }
 
 impl Command for PlayBgm {
-    /// This command will despawn the current soundtrack, then spawn a new one
-    /// if necessary.
+    /// This command will fade out the current soundtrack, then fade in a new
+    /// one if necessary. A zero fade cuts straight away.
     fn apply(self, world: &mut World) {
         world.run_system_once_with(self, play_bgm);
     }
@@ -33,46 +80,164 @@ impl Command for PlayBgm {
 fn play_bgm(
     In(config): In<PlayBgm>,
     mut commands: Commands,
-    bgm_query: Query<Entity, With<IsBgm>>,
+    bgm_query: Query<(Entity, &BgmVolume), With<IsBgm>>,
     bgm_handles: Res<BgmHandles>,
+    manifests: Res<Assets<MusicManifest>>,
+    intensity: Res<MusicIntensity>,
+    mix: Res<AudioMix>,
 ) {
-    for entity in bgm_query.iter() {
-        commands.entity(entity).despawn_recursive();
+    let (bgm_key, fade) = match config {
+        PlayBgm::Key { key, fade } => (Some(key), fade),
+        PlayBgm::Disable { fade } => (None, fade),
+    };
+
+    for (entity, volume) in bgm_query.iter() {
+        if fade.is_zero() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            // fade out from wherever it is now, even if it was still fading in
+            commands
+                .entity(entity)
+                .insert(BgmFade::new(fade, volume.fade, 0.0));
+        }
     }
 
-    let bgm_key = match config {
-        PlayBgm::Key(key) => key,
-        PlayBgm::Disable => return,
+    let Some(bgm_key) = bgm_key else {
+        return;
+    };
+
+    let Some(track) = bgm_handles
+        .manifest(&manifests)
+        .and_then(|manifest| manifest.tracks.get(&bgm_key))
+    else {
+        // the game's own tracks are checked on the loading screen too
+        warn!("Soundtrack not found: {bgm_key}");
+        return;
     };
 
-    commands.spawn((
-        AudioSourceBundle {
-            source: bgm_handles[&bgm_key].clone_weak(),
-            settings: PlaybackSettings {
-                mode: PlaybackMode::Loop,
-                ..default()
+    // Every stem starts together so they stay in sync, the ones outside the
+    // current intensity just start silent.
+    let fade_level = if fade.is_zero() { 1.0 } else { 0.0 };
+    for stem in &track.stems {
+        let layer = stem.intensity.map(|(min, max)| IntensityLayer {
+            min,
+            max,
+            blend: track.blend,
+        });
+        let volume = BgmVolume {
+            fade: fade_level,
+            layer: layer.map_or(1.0, |layer| layer.level(intensity.value)),
+        };
+        let mut bgm = commands.spawn((
+            AudioSourceBundle {
+                source: stem.source.clone_weak(),
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Loop,
+                    volume: mix.volume(AudioChannel::Music, volume.fade * volume.layer),
+                    ..default()
+                },
+            },
+            MixedAudio {
+                channel: AudioChannel::Music,
+                volume: volume.fade * volume.layer,
             },
-        },
-        IsBgm,
-    ));
+            volume,
+            IsBgm,
+        ));
+        if let Some(layer) = layer {
+            bgm.insert(layer);
+        }
+        if !fade.is_zero() {
+            bgm.insert(BgmFade::new(fade, 0.0, 1.0));
+        }
+    }
+}
+
+/// Step every fade. Real time is used so that music keeps fading while the
+/// game is paused.
+fn fade_bgm(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut fading: Query<(Entity, &mut BgmFade, &mut BgmVolume)>,
+) {
+    for (entity, mut fade, mut volume) in fading.iter_mut() {
+        fade.timer.tick(time.delta());
+        volume.fade = fade.volume();
+
+        if fade.timer.finished() {
+            if fade.to <= 0.0 {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                commands.entity(entity).remove::<BgmFade>();
+            }
+        }
+    }
+}
+
+/// Fade layers in and out as the music intensity moves through their ranges.
+fn follow_intensity(
+    time: Res<Time<Real>>,
+    intensity: Res<MusicIntensity>,
+    mut layers: Query<(&IntensityLayer, &mut BgmVolume)>,
+) {
+    for (layer, mut volume) in layers.iter_mut() {
+        let target = layer.level(intensity.value);
+        let step = time.delta_seconds() / layer.blend.max(f32::EPSILON);
+        let level = volume.layer + (target - volume.layer).clamp(-step, step);
+        // only touch the volume when it moves, so idle stems aren't updated every frame
+        if level != volume.layer {
+            volume.layer = level;
+        }
+    }
+}
+
+/// Pass volume changes on to the sinks.
+fn apply_bgm_volume(
+    mix: Res<AudioMix>,
+    global_volume: Res<GlobalVolume>,
+    mut stems: Query<(&BgmVolume, &mut MixedAudio, Option<&AudioSink>), Changed<BgmVolume>>,
+) {
+    for (volume, mut mixed, sink) in stems.iter_mut() {
+        mixed.volume = volume.fade * volume.layer;
+        // the sink only exists once the audio has started playing
+        if let Some(sink) = sink {
+            let volume = mix.volume(mixed.channel, mixed.volume);
+            sink.set_volume(global_volume.volume.get() * volume.get());
+        }
+    }
 }
 
 /// An extension trait with convenience methods for soundtrack commands.
 pub trait BgmCommands {
-    /// Play a soundtrack, replacing the current one.
+    /// Play a soundtrack, crossfading from the current one.
     /// Soundtracks will loop.
-    fn play_bgm(&mut self, name: impl Into<String>);
+    fn play_bgm(&mut self, name: impl Into<String>) {
+        self.crossfade_bgm(name, DEFAULT_CROSSFADE);
+    }
+
+    /// Play a soundtrack, crossfading from the current one over `duration`.
+    /// A zero duration replaces it straight away.
+    fn crossfade_bgm(&mut self, name: impl Into<String>, duration: Duration);
 
-    /// Stop the current soundtrack.
-    fn stop_bgm(&mut self);
+    /// Fade out the current soundtrack.
+    fn stop_bgm(&mut self) {
+        self.fade_out_bgm(DEFAULT_FADE_OUT);
+    }
+
+    /// Fade out the current soundtrack over `duration`.
+    /// A zero duration stops it straight away.
+    fn fade_out_bgm(&mut self, duration: Duration);
 }
 
 impl BgmCommands for Commands<'_, '_> {
-    fn play_bgm(&mut self, name: impl Into<String>) {
-        self.add(PlayBgm::Key(name.into()));
+    fn crossfade_bgm(&mut self, name: impl Into<String>, duration: Duration) {
+        self.add(PlayBgm::Key {
+            key: name.into(),
+            fade: duration,
+        });
     }
 
-    fn stop_bgm(&mut self) {
-        self.add(PlayBgm::Disable);
+    fn fade_out_bgm(&mut self, duration: Duration) {
+        self.add(PlayBgm::Disable { fade: duration });
     }
 }
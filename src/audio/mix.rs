@@ -0,0 +1,108 @@
+//! Volume mixing.
+//!
+//! Every sound plays on a channel, either music or effects. The [`AudioMix`]
+//! decides how loud each channel is on top of the master volume, and changes to
+//! it are applied to sounds that are already playing as well as new ones.
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    game::options::GameOptions,
+    input::{action_just_pressed, Action},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AudioMix>()
+        .register_type::<AudioMix>()
+        .register_type::<MixedAudio>()
+        .add_systems(
+            Update,
+            (
+                sync_with_options.run_if(resource_changed::<GameOptions>),
+                toggle_mute.run_if(action_just_pressed(Action::Mute)),
+                apply_audio_mix.run_if(resource_changed::<AudioMix>),
+            )
+                .chain(),
+        );
+}
+
+/// The channels sounds can be mixed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum AudioChannel {
+    Music,
+    Effects,
+}
+
+/// How loud each channel is, from 0 (silent) to 1 (full).
+/// The volumes follow the [`GameOptions`], muting only lasts for the session.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct AudioMix {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioMix {
+    fn default() -> Self {
+        AudioMix {
+            master: 1.0,
+            music: 1.0,
+            effects: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl AudioMix {
+    /// The volume to play a sound at on `channel`, given its own `volume`.
+    pub fn volume(&self, channel: AudioChannel, volume: f32) -> Volume {
+        if self.muted {
+            return Volume::ZERO;
+        }
+        let channel_volume = match channel {
+            AudioChannel::Music => self.music,
+            AudioChannel::Effects => self.effects,
+        };
+        Volume::new(volume * self.master * channel_volume)
+    }
+}
+
+/// Which channel a playing sound is on, and its own volume before mixing.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct MixedAudio {
+    pub channel: AudioChannel,
+    pub volume: f32,
+}
+
+fn sync_with_options(options: Res<GameOptions>, mut mix: ResMut<AudioMix>) {
+    mix.master = options.master_volume;
+    mix.music = options.music_volume;
+    mix.effects = options.effects_volume;
+}
+
+fn toggle_mute(mut mix: ResMut<AudioMix>) {
+    mix.muted = !mix.muted;
+    info!("Audio {}", if mix.muted { "muted" } else { "unmuted" });
+}
+
+/// New sounds pick up the mix when they are spawned, so only sounds that are
+/// already playing need their volume changing.
+fn apply_audio_mix(
+    mix: Res<AudioMix>,
+    global_volume: Res<GlobalVolume>,
+    sinks: Query<(&AudioSink, &MixedAudio)>,
+    spatial_sinks: Query<(&SpatialAudioSink, &MixedAudio)>,
+) {
+    let sink_volume = |mixed: &MixedAudio| {
+        global_volume.volume.get() * mix.volume(mixed.channel, mixed.volume).get()
+    };
+    for (sink, mixed) in sinks.iter() {
+        sink.set_volume(sink_volume(mixed));
+    }
+    for (sink, mixed) in spatial_sinks.iter() {
+        sink.set_volume(sink_volume(mixed));
+    }
+}
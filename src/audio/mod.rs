@@ -1,10 +1,15 @@
 //! Functionality relating to playing audio in the game.
 
 pub mod bgm;
+pub mod mix;
+pub mod music;
 pub mod sfx;
 
 use bevy::prelude::*;
 
+/// The volume everything is played at when the [`mix::AudioMix`] is at full volume.
+pub const GLOBAL_VOLUME: f32 = 0.3;
+
 pub fn plugin(app: &mut App) {
-    app.add_plugins((sfx::plugin, bgm::plugin));
+    app.add_plugins((sfx::plugin, bgm::plugin, mix::plugin, music::plugin));
 }
@@ -0,0 +1,291 @@
+//! Soundtracks described by a music manifest, and the intensity that drives
+//! gameplay music.
+//!
+//! Each track in the manifest is made of one or more stems that play together.
+//! A stem with an intensity range is only heard while the [`MusicIntensity`] is
+//! inside that range, so a track can blend layers in and out or switch between
+//! sections as the action heats up. Stingers are one-off cues played over the
+//! music, like the one on the player's death.
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt as _, LoadContext},
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use super::mix::{AudioChannel, AudioMix, MixedAudio};
+use crate::{
+    assets::RonAssetError,
+    game::{enemy::Enemy, player::Player},
+    prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<MusicManifest>()
+        .register_asset_loader(MusicManifestLoader)
+        .init_resource::<MusicIntensity>()
+        .add_systems(
+            Update,
+            update_intensity.run_if(in_state(InGameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            play_death_stinger.run_if(on_event::<EndGameTriggered>()),
+        )
+        .add_systems(OnEnter(InGameState::Preparation), reset_intensity);
+}
+
+/// The key of the stinger played when the player dies.
+pub const STINGER_DEATH: &str = "death";
+
+/// Every stinger the game plays, checked against the manifest on the loading
+/// screen.
+const STINGERS: [&str; 1] = [STINGER_DEATH];
+
+/// How many enemies on screen count as full intensity.
+const CROWDED_ENEMY_COUNT: f32 = 12.0;
+
+/// Enemies further away than this don't add to the intensity.
+const DANGER_DISTANCE: f32 = 400.0;
+
+/// A player speed, in pixels per second, that counts as flying flat out.
+const FAST_PLAYER_SPEED: f32 = 400.0;
+
+/// How quickly the intensity follows the action, in seconds. Smoothing stops
+/// sections flapping back and forth when the action is on the edge of a range.
+const INTENSITY_SMOOTHING: f32 = 2.0;
+
+/// Every soundtrack and stinger in the game.
+#[derive(Asset, TypePath, Debug)]
+pub struct MusicManifest {
+    pub tracks: HashMap<String, MusicTrack>,
+    pub stingers: HashMap<String, Handle<AudioSource>>,
+}
+
+impl MusicManifest {
+    /// Everything wrong with the manifest, including tracks and stingers the
+    /// game plays that it doesn't have, and audio that didn't load.
+    pub fn problems(&self, sources: &Assets<AudioSource>) -> Vec<String> {
+        let mut problems: Vec<String> = BgmHandles::TRACKS
+            .iter()
+            .filter(|key| !self.tracks.contains_key(**key))
+            .map(|key| format!("missing track {key}"))
+            .collect();
+        problems.extend(
+            STINGERS
+                .iter()
+                .filter(|key| !self.stingers.contains_key(**key))
+                .map(|key| format!("missing stinger {key}")),
+        );
+        for (name, track) in &self.tracks {
+            if track.stems.is_empty() {
+                problems.push(format!("track {name} has no stems"));
+            }
+            if !track.blend.is_finite() || track.blend < 0.0 {
+                problems.push(format!("track {name} has an invalid blend {}", track.blend));
+            }
+            for (index, stem) in track.stems.iter().enumerate() {
+                if !sources.contains(&stem.source) {
+                    problems.push(format!("stem {index} of track {name} did not load"));
+                }
+                if let Some((min, max)) = stem.intensity {
+                    if !min.is_finite() || !max.is_finite() || min > max {
+                        problems.push(format!(
+                            "stem {index} of track {name} has an invalid intensity range ({min}, {max})"
+                        ));
+                    }
+                }
+            }
+        }
+        for (name, stinger) in &self.stingers {
+            if !sources.contains(stinger) {
+                problems.push(format!("stinger {name} did not load"));
+            }
+        }
+        problems
+    }
+}
+
+/// A soundtrack made of stems that play in sync.
+#[derive(Debug)]
+pub struct MusicTrack {
+    pub stems: Vec<MusicStem>,
+    /// How long a stem takes to fade in or out when the intensity moves in or
+    /// out of its range, in seconds
+    pub blend: f32,
+}
+
+#[derive(Debug)]
+pub struct MusicStem {
+    pub source: Handle<AudioSource>,
+    /// The range of intensity the stem is heard in, or always if `None`
+    pub intensity: Option<(f32, f32)>,
+}
+
+/// How intense the action is, from 0 (calm) to 1 (frantic).
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct MusicIntensity {
+    /// The smoothed intensity the music follows
+    pub value: f32,
+    /// The intensity of the latest frame
+    pub target: f32,
+}
+
+/// A stem that fades with the [`MusicIntensity`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct IntensityLayer {
+    pub min: f32,
+    pub max: f32,
+    /// Seconds to fade fully in or out
+    pub blend: f32,
+}
+
+impl IntensityLayer {
+    /// How loud the layer wants to be at `intensity`.
+    pub fn level(&self, intensity: f32) -> f32 {
+        if (self.min..=self.max).contains(&intensity) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Sum up how much danger the player is in, from the number of enemies, how
+/// close the nearest one is and how fast the player is flying.
+fn update_intensity(
+    time: Res<Time>,
+    mut intensity: ResMut<MusicIntensity>,
+    enemies: Query<&Transform, With<Enemy>>,
+    player: Query<(&Transform, &Velocity), With<Player>>,
+) {
+    let Ok((player_transform, player_velocity)) = player.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    let crowd = (enemies.iter().count() as f32 / CROWDED_ENEMY_COUNT).min(1.0);
+    let nearest = enemies
+        .iter()
+        .map(|transform| transform.translation.truncate().distance(player_position))
+        .fold(f32::INFINITY, f32::min);
+    let proximity = (1.0 - nearest / DANGER_DISTANCE).clamp(0.0, 1.0);
+    let speed = (player_velocity.linvel.length() / FAST_PLAYER_SPEED).min(1.0);
+
+    intensity.target = 0.4 * crowd + 0.4 * proximity + 0.2 * speed;
+    let follow = 1.0 - (-time.delta_seconds() / INTENSITY_SMOOTHING).exp();
+    intensity.value += (intensity.target - intensity.value) * follow;
+}
+
+fn reset_intensity(mut intensity: ResMut<MusicIntensity>) {
+    *intensity = MusicIntensity::default();
+}
+
+fn play_death_stinger(
+    mut commands: Commands,
+    bgm_handles: Res<BgmHandles>,
+    manifests: Res<Assets<MusicManifest>>,
+    mix: Res<AudioMix>,
+) {
+    let Some(stinger) = bgm_handles
+        .manifest(&manifests)
+        .and_then(|manifest| manifest.stingers.get(STINGER_DEATH))
+    else {
+        // a missing stinger is reported when the manifest is checked on the
+        // loading screen
+        return;
+    };
+    commands.spawn((
+        AudioBundle {
+            source: stinger.clone_weak(),
+            settings: PlaybackSettings {
+                volume: mix.volume(AudioChannel::Music, 1.0),
+                ..PlaybackSettings::DESPAWN
+            },
+        },
+        MixedAudio {
+            channel: AudioChannel::Music,
+            volume: 1.0,
+        },
+    ));
+}
+
+/// The manifest as it is written in the data file, with paths instead of handles.
+#[derive(Deserialize)]
+struct MusicManifestFile {
+    tracks: HashMap<String, MusicTrackFile>,
+    #[serde(default)]
+    stingers: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct MusicTrackFile {
+    stems: Vec<MusicStemFile>,
+    #[serde(default = "default_blend")]
+    blend: f32,
+}
+
+fn default_blend() -> f32 {
+    2.0
+}
+
+#[derive(Deserialize)]
+struct MusicStemFile {
+    path: String,
+    #[serde(default)]
+    intensity: Option<(f32, f32)>,
+}
+
+/// Loads a music manifest, along with every audio file it mentions.
+struct MusicManifestLoader;
+
+impl AssetLoader for MusicManifestLoader {
+    type Asset = MusicManifest;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<MusicManifest, RonAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: MusicManifestFile = ron::de::from_bytes(&bytes)?;
+
+        // The audio is loaded as a dependency of the manifest, so the manifest
+        // only counts as loaded once all of its music has loaded too.
+        let tracks = file
+            .tracks
+            .into_iter()
+            .map(|(name, track)| {
+                let stems = track
+                    .stems
+                    .into_iter()
+                    .map(|stem| MusicStem {
+                        source: load_context.load(stem.path),
+                        intensity: stem.intensity,
+                    })
+                    .collect();
+                let track = MusicTrack {
+                    stems,
+                    blend: track.blend,
+                };
+                (name, track)
+            })
+            .collect();
+        let stingers = file
+            .stingers
+            .into_iter()
+            .map(|(name, path)| (name, load_context.load(path)))
+            .collect();
+
+        Ok(MusicManifest { tracks, stingers })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["music.ron"]
+    }
+}
@@ -12,44 +12,291 @@
 //! for sound effects corresponding to objects loaded from a data file.
 //!
 //! This pattern is taken from the [Bevy example for sound effects](https://github.com/bevyengine/bevy/pull/14554).
+//!
+//! Sound effects can also be played at a position in the world with
+//! `play_sfx_at`. Those are panned and attenuated by Bevy's spatial audio,
+//! heard from a listener that follows the player around the arena.
+//!
+//! Sound effects are looked up by a logical name, like `enemy.arrive`, in the
+//! [`SfxManifest`]. Each name maps to one or more files, picked at random,
+//! with a range of volumes and pitches to vary it every time it plays.
+//!
+//! Every sound effect takes a voice from [`SfxVoices`], which caps how many
+//! copies of a sound play at once, how quickly it can retrigger and how many
+//! sound effects play in total. When there are no voices left, a new sound
+//! steals one from a sound of lower or equal priority, or isn't played.
 
-use bevy::{ecs::world::Command, prelude::*};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt as _, LoadContext},
+    audio::SpatialScale,
+    ecs::world::Command,
+    prelude::*,
+    utils::HashMap,
+};
 use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use super::mix::{AudioChannel, AudioMix, MixedAudio};
+use crate::{
+    assets::{RonAssetError, SfxHandles},
+    game::{player::Player, resources::GameRng},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<SfxManifest>()
+        .register_asset_loader(SfxManifestLoader)
+        .init_resource::<SfxVoices>()
+        .add_systems(Startup, spawn_listener)
+        .add_systems(Update, follow_player);
+}
+
+/// Sounds closer to the listener than this, in pixels, play at full volume.
+/// Further away they get quieter in proportion to the distance.
+const FULL_VOLUME_DISTANCE: f32 = 200.0;
+
+/// How far apart the listener's ears are, in pixels. A wide gap makes sounds
+/// on either side of the player clearly pan left or right.
+const EAR_GAP: f32 = 400.0;
+
+/// The ears that positioned sound effects are heard from.
+#[derive(Component, Debug)]
+struct SfxListener;
+
+fn spawn_listener(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Sfx Listener"),
+        SpatialListener::new(EAR_GAP),
+        TransformBundle::default(),
+        SfxListener,
+    ));
+}
 
-use crate::assets::SfxHandles;
+/// Keep the listener on the player, or in the middle of the arena when there
+/// isn't one. The listener doesn't turn with the ship, so left always sounds left.
+fn follow_player(
+    player: Query<&Transform, (With<Player>, Without<SfxListener>)>,
+    mut listener: Query<&mut Transform, With<SfxListener>>,
+) {
+    let position = player
+        .get_single()
+        .map_or(Vec3::ZERO, |transform| transform.translation);
+    for mut transform in listener.iter_mut() {
+        if transform.translation != position {
+            transform.translation = position;
+        }
+    }
+}
+
+/// How a sound effect shares the available voices.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct VoiceLimit {
+    /// How many copies of the sound can play at once. Another copy replaces
+    /// the oldest one.
+    pub max_voices: usize,
+    /// Seconds before the sound can play again, retriggers sooner are dropped
+    pub min_interval: f32,
+    /// Sounds can only steal voices from sounds with the same or lower priority
+    pub priority: u8,
+}
+
+impl Default for VoiceLimit {
+    fn default() -> Self {
+        VoiceLimit {
+            max_voices: 4,
+            min_interval: 0.05,
+            priority: 1,
+        }
+    }
+}
+
+/// A sound effect that is playing.
+#[derive(Component, Debug, Clone)]
+pub struct SfxVoice {
+    pub key: String,
+    pub priority: u8,
+    /// When it started, in real seconds since startup
+    pub started: f64,
+}
+
+/// Hands out voices to sound effects, keeping noisy scenes readable and the
+/// number of audio entities bounded.
+#[derive(Resource, Debug)]
+pub struct SfxVoices {
+    /// The most sound effects that play at once
+    pub max_voices: usize,
+    last_played: HashMap<String, f64>,
+}
+
+impl Default for SfxVoices {
+    fn default() -> Self {
+        SfxVoices {
+            max_voices: 16,
+            last_played: HashMap::new(),
+        }
+    }
+}
+
+impl SfxVoices {
+    /// Take a voice for `key`, stopping the sounds it steals from, or `None`
+    /// if it shouldn't play right now.
+    fn claim(&mut self, key: &str, limit: VoiceLimit, world: &mut World) -> Option<SfxVoice> {
+        let now = world.resource::<Time<Real>>().elapsed_seconds_f64();
+        if self
+            .last_played
+            .get(key)
+            .is_some_and(|last| now - last < f64::from(limit.min_interval))
+        {
+            return None;
+        }
+
+        let mut playing: Vec<(Entity, SfxVoice)> = world
+            .query::<(Entity, &SfxVoice)>()
+            .iter(world)
+            .map(|(entity, voice)| (entity, voice.clone()))
+            .collect();
+        let mut stolen = Vec::new();
+
+        // too many copies of this sound, so the oldest makes way
+        let copies = playing.iter().filter(|(_, voice)| voice.key == key);
+        if copies.clone().count() >= limit.max_voices {
+            let oldest = copies
+                .min_by(|(_, a), (_, b)| a.started.total_cmp(&b.started))
+                .map(|(entity, _)| *entity)?;
+            stolen.push(oldest);
+            playing.retain(|(entity, _)| *entity != oldest);
+        }
+
+        // too many sounds overall, so steal from the least important, oldest first
+        if playing.len() >= self.max_voices {
+            let victim = playing
+                .iter()
+                .filter(|(_, voice)| voice.priority <= limit.priority)
+                .min_by(|(_, a), (_, b)| {
+                    a.priority
+                        .cmp(&b.priority)
+                        .then(a.started.total_cmp(&b.started))
+                })
+                .map(|(entity, _)| *entity)?;
+            stolen.push(victim);
+        }
+
+        for entity in stolen {
+            world.entity_mut(entity).despawn_recursive();
+        }
+        self.last_played.insert(key.to_string(), now);
+        Some(SfxVoice {
+            key: key.to_string(),
+            priority: limit.priority,
+            started: now,
+        })
+    }
+}
+
+/// Every sound effect in the game, by name.
+#[derive(Asset, TypePath, Debug)]
+pub struct SfxManifest {
+    pub sounds: HashMap<String, SoundEffect>,
+}
+
+impl SfxManifest {
+    /// Everything wrong with the manifest, including sounds the game plays
+    /// that it doesn't have.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = SfxHandles::KEYS
+            .iter()
+            .filter(|key| !self.sounds.contains_key(**key))
+            .map(|key| format!("missing sound effect {key}"))
+            .collect();
+        for (name, sound) in &self.sounds {
+            if sound.sources.is_empty() {
+                problems.push(format!("{name} has no files"));
+            }
+            let (min, max) = sound.volume;
+            if min < 0.0 || min > max {
+                problems.push(format!("{name} has an invalid volume range ({min}, {max})"));
+            }
+            let (min, max) = sound.pitch;
+            if min <= 0.0 || min > max {
+                problems.push(format!("{name} has an invalid pitch range ({min}, {max})"));
+            }
+        }
+        problems
+    }
+}
 
-pub(super) fn plugin(_app: &mut App) {
-    // No setup required for this plugin.
-    // It's still good to have a function here so that we can add some setup
-    // later if needed.
+/// One named sound effect and the ways it varies.
+#[derive(Debug, Clone)]
+pub struct SoundEffect {
+    /// Variations, one is picked at random each time
+    pub sources: Vec<Handle<AudioSource>>,
+    /// (min, max) volume multiplier
+    pub volume: (f32, f32),
+    /// (min, max) playback speed multiplier, which also changes the pitch
+    pub pitch: (f32, f32),
+    pub voices: VoiceLimit,
 }
 
-impl SfxHandles {
-    /// Plays a random sound effect matching the given name.
+impl SoundEffect {
+    /// Plays a random variation of the sound.
     ///
     /// When defining the settings for this method, we almost always want to use
     /// [`PlaybackMode::Despawn`](bevy::audio::PlaybackMode). Every time a
     /// sound effect is played, a new entity is generated. Once the sound effect
     /// is complete, the entity should be cleaned up, rather than looping or
     /// sitting around uselessly.
-    fn play(&mut self, name: impl AsRef<str>, world: &mut World, settings: PlaybackSettings) {
-        let name = name.as_ref();
-        if let Some(sfx_list) = self.get_mut(name) {
-            // If we need precise control over the randomization order of our sound effects,
-            // we can store the RNG as a resource and modify these functions to take it as
-            // an argument.
-            let rng = &mut rand::thread_rng();
-            let random_sfx = sfx_list.choose(rng).unwrap();
-
-            // We don't need a (slightly) more expensive strong handle here (which is used
-            // to keep an asset loaded in memory), because a copy is always
-            // stored in the `SfxHandles` resource.
-            let source = random_sfx.clone_weak();
-
-            world.spawn(AudioBundle { source, settings });
-        } else {
-            warn!("Sound effect not found: {name}");
+    ///
+    /// Sounds with a `position` are spatial, so they are panned and attenuated
+    /// relative to the player.
+    fn play(
+        &self,
+        world: &mut World,
+        mut settings: PlaybackSettings,
+        position: Option<Vec2>,
+        voice: SfxVoice,
+    ) {
+        // Variations and jitter are picked from the cosmetic stream so that
+        // audio never shifts the gameplay random sequence.
+        let mut rng = world.resource_mut::<GameRng>();
+        let Some(source) = self.sources.choose(&mut rng.cosmetic) else {
+            return;
+        };
+        let volume = jitter(&mut rng.cosmetic, self.volume);
+        settings.speed *= jitter(&mut rng.cosmetic, self.pitch);
+
+        // We don't need a (slightly) more expensive strong handle here (which is used
+        // to keep an asset loaded in memory), because a copy is always
+        // stored in the manifest.
+        let source = source.clone_weak();
+
+        // The settings hold the sound's own volume, the mix is applied on top.
+        let mixed = MixedAudio {
+            channel: AudioChannel::Effects,
+            volume: settings.volume.get() * volume,
+        };
+        settings.volume = world
+            .resource::<AudioMix>()
+            .volume(mixed.channel, mixed.volume);
+
+        let mut sfx = world.spawn((mixed, voice));
+        if let Some(position) = position {
+            settings.spatial = true;
+            settings.spatial_scale = Some(SpatialScale::new_2d(1.0 / FULL_VOLUME_DISTANCE));
+            sfx.insert(TransformBundle::from_transform(
+                Transform::from_translation(position.extend(0.0)),
+            ));
         }
+        sfx.insert(AudioBundle { source, settings });
+    }
+}
+
+/// A random value in a (min, max) range. Invalid ranges, which are reported on
+/// the loading screen, give the minimum.
+fn jitter(rng: &mut impl rand::Rng, (min, max): (f32, f32)) -> f32 {
+    if min < max {
+        rng.gen_range(min..=max)
+    } else {
+        min
     }
 }
 
@@ -57,24 +304,57 @@ impl SfxHandles {
 struct PlaySfx {
     name: String,
     settings: PlaybackSettings,
+    position: Option<Vec2>,
 }
 
 impl Command for PlaySfx {
     fn apply(self, world: &mut World) {
         // If you need more complex behavior, use `world.run_system_once_with`,
         // as demonstrated with `PlayBgm`.
-        world.resource_scope(|world, mut sfx: Mut<SfxHandles>| {
-            sfx.play(self.name, world, self.settings);
+        // without the sound effect assets, as in tests, there is nothing to play
+        let (Some(manifests), Some(handles)) = (
+            world.get_resource::<Assets<SfxManifest>>(),
+            world.get_resource::<SfxHandles>(),
+        ) else {
+            return;
+        };
+        let sound = handles
+            .manifest(manifests)
+            .and_then(|manifest| manifest.sounds.get(&self.name))
+            .cloned();
+        // Missing sounds are reported when the manifest is checked on the
+        // loading screen, so there is no need to warn every time.
+        let Some(sound) = sound else {
+            return;
+        };
+
+        let voice = world.resource_scope(|world, mut voices: Mut<SfxVoices>| {
+            voices.claim(&self.name, sound.voices, world)
         });
+        let Some(voice) = voice else {
+            return;
+        };
+        sound.play(world, self.settings, self.position, voice);
     }
 }
 
 /// An extension trait with convenience methods for sound effect commands.
 pub trait SfxCommands {
-    fn play_sfx_with_settings(&mut self, name: impl Into<String>, settings: PlaybackSettings);
+    /// Play a sound effect, at a world position if one is given.
+    fn play_sfx_with_settings(
+        &mut self,
+        name: impl Into<String>,
+        settings: PlaybackSettings,
+        position: Option<Vec2>,
+    );
 
     fn play_sfx(&mut self, name: impl Into<String>) {
-        self.play_sfx_with_settings(name, PlaybackSettings::DESPAWN);
+        self.play_sfx_with_settings(name, PlaybackSettings::DESPAWN, None);
+    }
+
+    /// Play a sound effect that is heard from where it happened in the world.
+    fn play_sfx_at(&mut self, name: impl Into<String>, position: Vec2) {
+        self.play_sfx_with_settings(name, PlaybackSettings::DESPAWN, Some(position));
     }
 }
 
@@ -82,8 +362,161 @@ impl SfxCommands for Commands<'_, '_> {
     // By accepting an `Into<String>` here, we can be flexible about what we want to
     // accept: &str literals are better for prototyping and data-driven sound
     // effects, but enums are nicer for special-cased effects
-    fn play_sfx_with_settings(&mut self, name: impl Into<String>, settings: PlaybackSettings) {
+    fn play_sfx_with_settings(
+        &mut self,
+        name: impl Into<String>,
+        settings: PlaybackSettings,
+        position: Option<Vec2>,
+    ) {
         let name = name.into();
-        self.add(PlaySfx { name, settings });
+        self.add(PlaySfx {
+            name,
+            settings,
+            position,
+        });
+    }
+}
+
+/// The manifest as it is written in the data file, with paths instead of handles.
+#[derive(Deserialize)]
+struct SfxManifestFile {
+    sounds: HashMap<String, SoundEffectFile>,
+}
+
+#[derive(Deserialize)]
+struct SoundEffectFile {
+    files: Vec<String>,
+    #[serde(default = "no_jitter")]
+    volume: (f32, f32),
+    #[serde(default = "no_jitter")]
+    pitch: (f32, f32),
+    #[serde(default)]
+    voices: VoiceLimit,
+}
+
+fn no_jitter() -> (f32, f32) {
+    (1.0, 1.0)
+}
+
+/// Loads a sound effect manifest, along with every audio file it mentions.
+struct SfxManifestLoader;
+
+impl AssetLoader for SfxManifestLoader {
+    type Asset = SfxManifest;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<SfxManifest, RonAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: SfxManifestFile = ron::de::from_bytes(&bytes)?;
+
+        // The audio is loaded as a dependency of the manifest, so the manifest
+        // only counts as loaded once all of its sounds have loaded too.
+        let sounds = file
+            .sounds
+            .into_iter()
+            .map(|(name, sound)| {
+                let sources = sound
+                    .files
+                    .into_iter()
+                    .map(|path| load_context.load(path))
+                    .collect();
+                let sound = SoundEffect {
+                    sources,
+                    volume: sound.volume,
+                    pitch: sound.pitch,
+                    voices: sound.voices,
+                };
+                (name, sound)
+            })
+            .collect();
+
+        Ok(SfxManifest { sounds })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sfx.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max_voices: usize, min_interval: f32, priority: u8) -> VoiceLimit {
+        VoiceLimit {
+            max_voices,
+            min_interval,
+            priority,
+        }
+    }
+
+    fn world_playing(voices: &[(&str, u8, f64)]) -> (World, Vec<Entity>) {
+        let mut world = World::new();
+        world.insert_resource(Time::<Real>::default());
+        let entities = voices
+            .iter()
+            .map(|(key, priority, started)| {
+                world
+                    .spawn(SfxVoice {
+                        key: key.to_string(),
+                        priority: *priority,
+                        started: *started,
+                    })
+                    .id()
+            })
+            .collect();
+        (world, entities)
+    }
+
+    #[test]
+    fn a_quick_retrigger_is_dropped() {
+        let (mut world, _) = world_playing(&[]);
+        let mut voices = SfxVoices::default();
+        assert!(voices.claim("hit", limit(4, 0.05, 1), &mut world).is_some());
+        assert!(voices.claim("hit", limit(4, 0.05, 1), &mut world).is_none());
+        assert!(voices
+            .claim("bump", limit(4, 0.05, 1), &mut world)
+            .is_some());
+    }
+
+    #[test]
+    fn too_many_copies_replace_the_oldest() {
+        let (mut world, playing) = world_playing(&[("hit", 1, 1.0), ("hit", 1, 0.0)]);
+        let mut voices = SfxVoices::default();
+        assert!(voices.claim("hit", limit(2, 0.0, 1), &mut world).is_some());
+        assert!(world.get_entity(playing[0]).is_some());
+        assert!(world.get_entity(playing[1]).is_none());
+    }
+
+    #[test]
+    fn a_full_mix_steals_from_the_least_important() {
+        let (mut world, playing) = world_playing(&[("music", 2, 0.0), ("ping", 0, 1.0)]);
+        let mut voices = SfxVoices {
+            max_voices: 2,
+            ..default()
+        };
+        assert!(voices.claim("hit", limit(4, 0.0, 1), &mut world).is_some());
+        assert!(world.get_entity(playing[0]).is_some());
+        assert!(world.get_entity(playing[1]).is_none());
+    }
+
+    #[test]
+    fn a_full_mix_of_more_important_sounds_drops_the_new_one() {
+        let (mut world, playing) = world_playing(&[("music", 2, 0.0), ("alarm", 2, 1.0)]);
+        let mut voices = SfxVoices {
+            max_voices: 2,
+            ..default()
+        };
+        assert!(voices.claim("hit", limit(4, 0.0, 1), &mut world).is_none());
+        assert!(playing
+            .iter()
+            .all(|entity| world.get_entity(*entity).is_some()));
     }
 }
@@ -4,8 +4,9 @@ use bevy::{
     dev_tools::states::log_transitions, input::common_conditions::input_toggle_active, prelude::*,
 };
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_rapier2d::prelude::ExternalForce;
 
-use crate::screens::Screen;
+use crate::{game::flocking::FlockingForce, screens::Screen};
 
 pub(super) fn plugin(app: &mut App) {
     // Print state transitions in dev builds
@@ -13,4 +14,33 @@ pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         WorldInspectorPlugin::default().run_if(input_toggle_active(false, KeyCode::KeyI)),
     ));
+    // Show the flocking and thrust of each enemy
+    app.add_systems(
+        Update,
+        draw_flocking_forces.run_if(input_toggle_active(false, KeyCode::KeyF)),
+    );
+}
+
+/// Length in pixels of a full strength force arrow.
+const FORCE_ARROW_LENGTH: f32 = 40.0;
+
+/// Draw the flocking force of each flocking enemy in cyan, and the direction it
+/// is actually thrusting in red.
+fn draw_flocking_forces(
+    mut gizmos: Gizmos,
+    enemies: Query<(&GlobalTransform, &FlockingForce, &ExternalForce)>,
+) {
+    for (transform, flocking, thrust) in enemies.iter() {
+        let start = transform.translation().truncate();
+        gizmos.arrow_2d(
+            start,
+            start + flocking.0 * FORCE_ARROW_LENGTH,
+            Color::srgb(0.0, 1.0, 1.0),
+        );
+        gizmos.arrow_2d(
+            start,
+            start + thrust.force.normalize_or_zero() * FORCE_ARROW_LENGTH,
+            Color::srgb(1.0, 0.0, 0.0),
+        );
+    }
 }
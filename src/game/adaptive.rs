@@ -0,0 +1,237 @@
+//! Adaptive difficulty.
+//!
+//! On top of the chosen [`Difficulty`](super::difficulty::Difficulty), the
+//! adaptive director keeps an eye on how the player is coping: close calls
+//! with enemies, bumps into walls, hits taken, how long it has been since the
+//! last enemy arrived and how quickly the score is going up. Every few seconds
+//! it turns that into a tension value and nudges enemy strength and spawn pacing
+//! to keep the tension inside a target band. Every decision is logged, and the
+//! whole curve is saved at the end of the run so it can be reviewed, even from
+//! release builds where the logs are compiled out.
+
+use super::{
+    enemy::{Enemy, EnemyState},
+    player::Player,
+};
+use crate::{persistence::Persistence, prelude::*};
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AdaptiveDirector>()
+        .add_systems(
+            FixedUpdate,
+            run_adaptive_director
+                .in_set(AppSet::Update)
+                .run_if(in_state(InGameState::Playing)),
+        )
+        .add_systems(OnEnter(InGameState::Preparation), reset_adaptive_director)
+        .add_systems(OnEnter(InGameState::Summary), save_tension_curve);
+}
+
+/// The key the last run's tension curve is saved under.
+const CURVE_STORAGE_KEY: &str = "director_curve";
+
+/// Seconds between each review of the tension
+const REVIEW_SECONDS: f32 = 5.0;
+/// An enemy closer than this to the player, in pixels, counts as a close call
+const CLOSE_CALL_DISTANCE: f32 = 60.0;
+/// After this many seconds without a new enemy the game counts as calm
+const QUIET_SECONDS: f32 = 10.0;
+/// Scoring this many points per second counts as the player being fully in control
+const COMFORTABLE_SCORE_RATE: f32 = 5.0;
+/// How much each review moves the bias and spawn interval multipliers
+const ADJUSTMENT_STEP: f32 = 0.05;
+/// How far the multipliers can move away from the chosen difficulty
+const ADJUSTMENT_RANGE: (f32, f32) = (0.6, 1.5);
+
+/// Which way a review nudged the difficulty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TensionDecision {
+    /// The tension was below the target band
+    Harder,
+    /// The tension was above the target band
+    Easier,
+    /// The tension was inside the target band
+    Hold,
+}
+
+/// What the director saw during one review, and what it decided.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TensionSample {
+    /// Game time of the review, in seconds
+    pub time: f32,
+    pub tension: f32,
+    pub decision: TensionDecision,
+    pub bias_scale: f32,
+    pub interval_scale: f32,
+}
+
+/// Watches how the player is doing and adjusts the difficulty to match.
+#[derive(Resource, Debug)]
+pub struct AdaptiveDirector {
+    /// The tension to aim for, 0 is bored and 1 is overwhelmed
+    pub target_band: (f32, f32),
+    /// Smoothed tension as of the last review
+    pub tension: f32,
+    /// Multiplier on the bias passed to the enemy stats
+    pub bias_scale: f32,
+    /// Multiplier on the gaps between spawns and between waves
+    pub interval_scale: f32,
+    /// Every review this run, oldest first
+    pub history: Vec<TensionSample>,
+    review: Timer,
+    close_call_seconds: f32,
+    wall_bumps: u32,
+    hits: u32,
+    seconds_since_spawn: f32,
+    score_at_review: f32,
+}
+
+impl Default for AdaptiveDirector {
+    fn default() -> Self {
+        AdaptiveDirector {
+            target_band: (0.35, 0.65),
+            tension: 0.5,
+            bias_scale: 1.0,
+            interval_scale: 1.0,
+            history: Vec::new(),
+            review: Timer::from_seconds(REVIEW_SECONDS, TimerMode::Repeating),
+            close_call_seconds: 0.0,
+            wall_bumps: 0,
+            hits: 0,
+            seconds_since_spawn: 0.0,
+            score_at_review: 0.0,
+        }
+    }
+}
+
+impl AdaptiveDirector {
+    /// Turn what happened since the last review into a tension value between 0 and 1.
+    fn sample_tension(&self, score_rate: f32) -> f32 {
+        let mut tension = self.close_call_seconds / REVIEW_SECONDS
+            + self.wall_bumps as f32 * 0.1
+            + self.hits as f32 * 0.5;
+        // nothing new to deal with for a while
+        if self.seconds_since_spawn > QUIET_SECONDS {
+            tension -= 0.2;
+        }
+        // scoring quickly means the player is on top of things
+        tension -= 0.2 * (score_rate / COMFORTABLE_SCORE_RATE).min(1.0);
+        tension.clamp(0.0, 1.0)
+    }
+}
+
+fn reset_adaptive_director(mut director: ResMut<AdaptiveDirector>) {
+    *director = AdaptiveDirector::default();
+}
+
+/// Keep track of how the player is doing, and adjust the difficulty every review.
+fn run_adaptive_director(
+    time: Res<Time>,
+    game_time: Res<GameTime>,
+    score: Res<Score>,
+    mut director: ResMut<AdaptiveDirector>,
+    players: Query<(Entity, &Transform), With<Player>>,
+    enemies: Query<(Entity, &Transform, &Enemy)>,
+    new_enemies: Query<(), Added<Enemy>>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut hit_events: EventReader<ShipHit>,
+) {
+    let Ok((player, player_transform)) = players.get_single() else {
+        return;
+    };
+    let delta = time.delta_seconds();
+
+    // close calls, counted as seconds spent with a moving enemy right next to the player
+    let close_enemies = enemies
+        .iter()
+        .filter(|(_, transform, enemy)| {
+            enemy.state == EnemyState::Moving
+                && transform.translation.distance(player_transform.translation)
+                    < CLOSE_CALL_DISTANCE
+        })
+        .count();
+    director.close_call_seconds += close_enemies as f32 * delta;
+
+    // the player bumping into anything that isn't an enemy is a wall
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(entity1, entity2, _) = *event {
+            let other = match (entity1 == player, entity2 == player) {
+                (true, _) => entity2,
+                (_, true) => entity1,
+                _ => continue,
+            };
+            if !enemies.contains(other) {
+                director.wall_bumps += 1;
+            }
+        }
+    }
+    director.hits += hit_events.read().filter(|event| event.id == player).count() as u32;
+
+    if new_enemies.is_empty() {
+        director.seconds_since_spawn += delta;
+    } else {
+        director.seconds_since_spawn = 0.0;
+    }
+
+    if !director.review.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let score_rate = (score.0 - director.score_at_review) / REVIEW_SECONDS;
+    let sample = director.sample_tension(score_rate);
+    director.tension += (sample - director.tension) * 0.5;
+
+    let (low, high) = director.target_band;
+    let (min, max) = ADJUSTMENT_RANGE;
+    let decision = if director.tension < low {
+        director.bias_scale = (director.bias_scale + ADJUSTMENT_STEP).min(max);
+        director.interval_scale = (director.interval_scale - ADJUSTMENT_STEP).max(min);
+        TensionDecision::Harder
+    } else if director.tension > high {
+        director.bias_scale = (director.bias_scale - ADJUSTMENT_STEP).max(min);
+        director.interval_scale = (director.interval_scale + ADJUSTMENT_STEP).min(max);
+        TensionDecision::Easier
+    } else {
+        TensionDecision::Hold
+    };
+    info!(
+        "Adaptive director at {:.0}s: tension {:.2} (close calls {:.1}s, wall bumps {}, hits {}, {:.0}s since spawn, score {:.0} at {:.1}/s) -> {decision:?}, bias x{:.2}, spawn interval x{:.2}",
+        game_time.time,
+        director.tension,
+        director.close_call_seconds,
+        director.wall_bumps,
+        director.hits,
+        director.seconds_since_spawn,
+        score.0,
+        score_rate,
+        director.bias_scale,
+        director.interval_scale,
+    );
+
+    let sample = TensionSample {
+        time: game_time.time,
+        tension: director.tension,
+        decision,
+        bias_scale: director.bias_scale,
+        interval_scale: director.interval_scale,
+    };
+    director.history.push(sample);
+    director.close_call_seconds = 0.0;
+    director.wall_bumps = 0;
+    director.hits = 0;
+    director.score_at_review = score.0;
+}
+
+/// Save the tension curve of the run that just ended, replacing the one
+/// before, and log it one review per line for dev builds.
+fn save_tension_curve(director: Res<AdaptiveDirector>, persistence: Res<Persistence>) {
+    persistence.save_json(CURVE_STORAGE_KEY, &director.history);
+    info!("Adaptive director curve: time, tension, decision, bias scale, spawn interval scale");
+    for sample in &director.history {
+        info!(
+            "{:.0}, {:.2}, {:?}, {:.2}, {:.2}",
+            sample.time, sample.tension, sample.decision, sample.bias_scale, sample.interval_scale
+        );
+    }
+}
@@ -6,71 +6,96 @@ use super::{
     player::Player,
 };
 use crate::{audio::sfx::SfxCommands, prelude::*};
-use bevy::{
-    audio::{PlaybackMode, Volume},
-    utils::hashbrown::Equivalent,
-};
+use bevy::color::palettes::css::SILVER;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
-        PostUpdate,
+        FixedUpdate,
         collision_events
             .in_set(AppSet::Update)
             .run_if(in_state(InGameState::Playing)),
     );
 }
 
-/// Trigger an end of game event when the player gets hit by an enemy
+/// Impacts slower than this, in pixels per second, don't do any damage
+const MIN_DAMAGE_SPEED: f32 = 120.0;
+/// Every this many pixels per second above the minimum deals one point of damage
+const SPEED_PER_DAMAGE: f32 = 240.0;
+
+/// A moving enemy always does at least this much damage to the player
+const MIN_PLAYER_DAMAGE: f32 = 0.5;
+
+/// Damage dealt by an impact at the given relative speed, in pixels per second
+fn impact_damage(relative_speed: f32) -> f32 {
+    (relative_speed - MIN_DAMAGE_SPEED).max(0.0) / SPEED_PER_DAMAGE
+}
+
+/// Damage enemies that hit something, and the player when a moving enemy hits them
 fn collision_events(
     mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
-    mut player_destroyed: EventWriter<EndGameTriggered>,
     mut ship_hit_events: EventWriter<ShipHit>,
-    players: Query<(Entity, &Velocity), With<Player>>,
-    enemies: Query<(Entity, &Enemy)>,
+    players: Query<(Entity, &Velocity, &Transform), With<Player>>,
+    enemies: Query<(&Enemy, &Transform)>,
+    velocities: Query<&Velocity>,
+    ships: Query<&Transform, Or<(With<Enemy>, With<Player>)>>,
 ) {
-    let (player, player_vel) = players
+    let (player, player_vel, player_transform) = players
         .get_single()
         .expect("Collision system found more than one player, this should not happen");
 
     for collision_event in collision_events.read() {
+        let (CollisionEvent::Started(entity1, entity2, _)
+        | CollisionEvent::Stopped(entity1, entity2, _)) = *collision_event;
+        // heard from the ship involved rather than the middle of a wall
+        let position = [entity1, entity2]
+            .into_iter()
+            .find_map(|entity| ships.get(entity).ok())
+            .map(|transform| transform.translation.truncate());
         commands.play_sfx_with_settings(
-            SfxHandles::PATH_SELECT,
-            PlaybackSettings {
-                mode: PlaybackMode::Despawn,
-                volume: Volume::new(0.5),
-                speed: randomise_speed(),
-                ..default()
-            },
+            SfxHandles::KEY_SHIP_BUMP,
+            PlaybackSettings::DESPAWN,
+            position,
         );
-        for (enemy_entity, enemy) in enemies.iter() {
-            if let CollisionEvent::Started(entity1, entity2, _) = collision_event {
-                if entity1.equivalent(&enemy_entity) {
-                    ship_hit_events.send(ShipHit { id: *entity1 });
-                    if entity2.equivalent(&player) && enemy.state == EnemyState::Moving {
-                        info!("Player collided with enemy");
-                        player_destroyed.send(EndGameTriggered);
-                    }
-                } else if entity2.equivalent(&enemy_entity) && enemy.state == EnemyState::Moving {
-                    ship_hit_events.send(ShipHit { id: *entity2 });
-                    if entity1.equivalent(&player) {
-                        info!("Player collided with enemy");
-                        player_destroyed.send(EndGameTriggered);
-                    }
-                } else if entity1.equivalent(&player) || entity2.equivalent(&player) {
-                    let velocity_magnitude = get_magnitude(player_vel);
-                    info!(
-                        "Player collided with something else with velocity {}",
-                        velocity_magnitude
-                    );
-                    commands.add_trauma(velocity_magnitude);
-                }
+        if !matches!(collision_event, CollisionEvent::Started(..)) {
+            continue;
+        }
+        // Walls have no velocity. The velocities here are from after the bounce,
+        // but with high restitution the bodies separate at nearly the speed they met.
+        let velocity = |entity| velocities.get(entity).map_or(Vec2::ZERO, |v| v.linvel);
+        let damage = impact_damage((velocity(entity1) - velocity(entity2)).length());
+
+        let mut enemy_hit = false;
+        for (ship, other) in [(entity1, entity2), (entity2, entity1)] {
+            let Ok((enemy, transform)) = enemies.get(ship) else {
+                continue;
+            };
+            enemy_hit = true;
+            ship_hit_events.send(ShipHit {
+                id: ship,
+                damage,
+                x: transform.translation.x,
+                y: transform.translation.y,
+                colour: enemy.colour,
+            });
+            if other == player && enemy.state == EnemyState::Moving {
+                info!("Player collided with enemy");
+                ship_hit_events.send(ShipHit {
+                    id: player,
+                    damage: damage.max(MIN_PLAYER_DAMAGE),
+                    x: player_transform.translation.x,
+                    y: player_transform.translation.y,
+                    colour: SILVER.into(),
+                });
             }
         }
+        if !enemy_hit && (entity1 == player || entity2 == player) {
+            let velocity_magnitude = get_magnitude(player_vel);
+            info!(
+                "Player collided with something else with velocity {}",
+                velocity_magnitude
+            );
+            commands.add_trauma(velocity_magnitude);
+        }
     }
 }
-
-fn randomise_speed() -> f32 {
-    let mut rng = rand::thread_rng();
-    rng.gen_range(0.5..1.5)
-}
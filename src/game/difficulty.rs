@@ -0,0 +1,177 @@
+//! Difficulty presets.
+//!
+//! The chosen [`Difficulty`] decides how strong enemies are, how quickly they
+//! get stronger as the score rises, how often they spawn, how the player's
+//! ship handles and how much each point is worth. It is picked on the
+//! preparation screen and kept between runs.
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Difficulty>()
+        .register_type::<Difficulty>();
+}
+
+/// The difficulty levels to choose from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum DifficultyLevel {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+    /// Tweaked by the player on the preparation screen
+    Custom,
+}
+
+impl DifficultyLevel {
+    /// Every level, in the order they are cycled through.
+    pub const ALL: [DifficultyLevel; 5] = [
+        DifficultyLevel::Easy,
+        DifficultyLevel::Normal,
+        DifficultyLevel::Hard,
+        DifficultyLevel::Insane,
+        DifficultyLevel::Custom,
+    ];
+
+    /// The level after this one, wrapping back round to the first.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|level| *level == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DifficultyLevel::Easy => "Easy",
+            DifficultyLevel::Normal => "Normal",
+            DifficultyLevel::Hard => "Hard",
+            DifficultyLevel::Insane => "Insane",
+            DifficultyLevel::Custom => "Custom",
+        }
+    }
+}
+
+/// Everything a difficulty level changes.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct DifficultySettings {
+    /// Multiplier on enemy speed, thrust, energy and recharge rate
+    pub enemy_strength: f32,
+    /// Score at which enemies spawn at their full stat ranges
+    pub bias_score: f32,
+    /// Lowest bias, used at the start of a run
+    pub bias_min: f32,
+    /// Highest bias, reached as the score keeps rising
+    pub bias_max: f32,
+    /// Multiplier on the gaps between spawns and between waves
+    pub spawn_interval: f32,
+    /// Multiplier on how many enemies each wave brings
+    pub wave_size: f32,
+    /// The player ship's top speed, in pixels per tick
+    pub player_max_speed: f32,
+    /// The player ship's mass
+    pub player_mass: f32,
+    /// The player ship's thrust
+    pub player_thrust: f32,
+    /// Damage the player can take before losing a life
+    pub hull: f32,
+    /// Extra lives on top of the first one
+    pub lives: u32,
+    /// Multiplier on every point scored
+    pub score_multiplier: f32,
+}
+
+impl DifficultySettings {
+    /// The settings for one of the preset levels. `Custom` starts from `Normal`.
+    pub fn preset(level: DifficultyLevel) -> Self {
+        let normal = DifficultySettings {
+            enemy_strength: 1.0,
+            bias_score: 100.0,
+            bias_min: 0.5,
+            bias_max: 2.0,
+            spawn_interval: 1.0,
+            wave_size: 1.0,
+            player_max_speed: 8.0,
+            player_mass: 1.3,
+            player_thrust: 15.0,
+            hull: 3.0,
+            lives: 2,
+            score_multiplier: 1.0,
+        };
+        match level {
+            DifficultyLevel::Easy => DifficultySettings {
+                enemy_strength: 0.8,
+                bias_score: 150.0,
+                bias_max: 1.5,
+                spawn_interval: 1.3,
+                wave_size: 0.75,
+                player_thrust: 17.0,
+                hull: 5.0,
+                lives: 3,
+                score_multiplier: 0.5,
+                ..normal
+            },
+            DifficultyLevel::Normal | DifficultyLevel::Custom => normal,
+            DifficultyLevel::Hard => DifficultySettings {
+                enemy_strength: 1.15,
+                bias_score: 75.0,
+                bias_min: 0.75,
+                bias_max: 2.5,
+                spawn_interval: 0.8,
+                wave_size: 1.25,
+                hull: 2.0,
+                lives: 1,
+                score_multiplier: 1.5,
+                ..normal
+            },
+            DifficultyLevel::Insane => DifficultySettings {
+                enemy_strength: 1.3,
+                bias_score: 50.0,
+                bias_min: 1.0,
+                bias_max: 3.0,
+                spawn_interval: 0.6,
+                wave_size: 1.5,
+                hull: 1.0,
+                lives: 0,
+                score_multiplier: 2.5,
+                ..normal
+            },
+        }
+    }
+
+    /// How far towards the top of their stat ranges new enemies roll, for the current score.
+    pub fn bias(&self, score: f32) -> f32 {
+        (score / self.bias_score).clamp(self.bias_min, self.bias_max)
+    }
+}
+
+/// The difficulty used for the next run.
+#[derive(Resource, Debug, Clone, Copy, Reflect)]
+#[reflect(Resource)]
+pub struct Difficulty {
+    pub level: DifficultyLevel,
+    /// Settings used when `level` is `Custom`
+    pub custom: DifficultySettings,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty {
+            level: DifficultyLevel::default(),
+            custom: DifficultySettings::preset(DifficultyLevel::Custom),
+        }
+    }
+}
+
+impl Difficulty {
+    /// The settings for the chosen level.
+    pub fn settings(&self) -> DifficultySettings {
+        match self.level {
+            DifficultyLevel::Custom => self.custom,
+            level => DifficultySettings::preset(level),
+        }
+    }
+}
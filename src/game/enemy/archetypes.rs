@@ -0,0 +1,155 @@
+//! Data-driven enemy archetypes.
+//!
+//! Every kind of enemy is described in `assets/data/enemies.archetypes.ron`,
+//! so new enemies can be added or tuned without recompiling. In `dev_native`
+//! builds the file is hot reloaded, and the next enemy to spawn picks up the
+//! changes.
+
+use super::resources::{EnemyShape, EnemyStrengthRange};
+use crate::{
+    assets::RonAssetApp as _,
+    game::{flocking::Flocking, steering::SteeringBehaviour},
+    prelude::*,
+};
+use rand::{distributions::WeightedIndex, prelude::Distribution as _, Rng};
+use serde::Deserialize;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_ron_asset::<EnemyArchetypes>(&["archetypes.ron"])
+        .add_systems(Update, validate_archetypes);
+}
+
+/// A list of enemy archetypes loaded from a data file.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct EnemyArchetypes {
+    pub archetypes: Vec<EnemyArchetype>,
+}
+
+/// Everything needed to spawn one kind of enemy.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemyArchetype {
+    /// Unique name, used to refer to the archetype from code and in logs
+    pub name: String,
+    /// Stat ranges that spawned enemies are rolled from
+    #[serde(default)]
+    pub stats: EnemyStrengthRange,
+    /// Outline the enemy is drawn with
+    #[serde(default)]
+    pub shape: EnemyShape,
+    /// Fill colour as sRGB components between 0 and 1
+    pub colour: (f32, f32, f32),
+    /// How much damage the enemy can take before it is destroyed
+    #[serde(default = "default_health")]
+    pub health: f32,
+    /// Multiplier applied to the score the player earns from this enemy
+    #[serde(default = "one")]
+    pub score_value: f32,
+    /// How the enemy moves
+    #[serde(default)]
+    pub behaviour: SteeringBehaviour,
+    /// How the enemy reacts to other enemies around it, off by default
+    #[serde(default)]
+    pub flocking: Flocking,
+    /// Relative chance of this archetype being picked when spawning
+    #[serde(default = "one")]
+    pub spawn_weight: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn default_health() -> f32 {
+    4.0
+}
+
+impl EnemyArchetype {
+    /// The fill colour of enemies of this archetype.
+    pub fn colour(&self) -> Color {
+        let (red, green, blue) = self.colour;
+        Color::srgb(red, green, blue)
+    }
+
+    /// Check that the archetype can be spawned. Invalid archetypes are skipped,
+    /// so a typo in the data file can't crash the game mid-run.
+    pub fn validate(&self) -> Result<(), String> {
+        self.stats.validate()?;
+        if !self.health.is_finite() || self.health <= 0.0 {
+            return Err(format!("health {} is invalid", self.health));
+        }
+        if !self.flocking.radius.is_finite() || self.flocking.radius <= 0.0 {
+            return Err(format!(
+                "flocking radius {} is invalid",
+                self.flocking.radius
+            ));
+        }
+        let weights = [
+            ("separation", self.flocking.separation),
+            ("alignment", self.flocking.alignment),
+            ("cohesion", self.flocking.cohesion),
+        ];
+        for (name, weight) in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(format!("flocking {name} {weight} is invalid"));
+            }
+        }
+        if !self.spawn_weight.is_finite() || self.spawn_weight < 0.0 {
+            return Err(format!("spawn_weight {} is invalid", self.spawn_weight));
+        }
+        Ok(())
+    }
+}
+
+impl EnemyArchetypes {
+    /// Pick a valid archetype at random, weighted by each archetype's spawn weight.
+    pub fn choose(&self, rng: &mut impl Rng) -> Option<&EnemyArchetype> {
+        let weights = self.archetypes.iter().map(|archetype| {
+            if archetype.validate().is_ok() {
+                archetype.spawn_weight
+            } else {
+                0.0
+            }
+        });
+        let index = WeightedIndex::new(weights).ok()?.sample(rng);
+        self.archetypes.get(index)
+    }
+
+    /// Find a valid archetype by name.
+    pub fn get(&self, name: &str) -> Option<&EnemyArchetype> {
+        self.archetypes
+            .iter()
+            .find(|archetype| archetype.name == name && archetype.validate().is_ok())
+    }
+
+    /// Whether [`EnemyArchetypes::choose`] has anything to pick from.
+    pub fn any_spawnable(&self) -> bool {
+        self.archetypes
+            .iter()
+            .any(|archetype| archetype.spawn_weight > 0.0 && archetype.validate().is_ok())
+    }
+}
+
+/// Report problems with the archetypes whenever the file is (re)loaded.
+fn validate_archetypes(
+    mut events: EventReader<AssetEvent<EnemyArchetypes>>,
+    assets: Res<Assets<EnemyArchetypes>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        let Some(asset) = assets.get(*id) else {
+            continue;
+        };
+        for archetype in &asset.archetypes {
+            if let Err(error) = archetype.validate() {
+                error!(
+                    "Enemy archetype '{}' will not spawn: {error}",
+                    archetype.name
+                );
+            }
+        }
+        info!("Loaded {} enemy archetypes", asset.archetypes.len());
+    }
+}
@@ -1,11 +1,13 @@
 //! Module for the enemy entities and systems
 
+pub mod archetypes;
 mod resources;
 mod systems;
 
+pub use systems::SpawnEnemy;
+
 use crate::prelude::*;
-use resources::EnemyStrengthRange;
-use systems::{enemy_hit, enemy_lifetime, spawn_enemy};
+use systems::{enemy_hit, enemy_lifetime, show_damage};
 
 /// A enemy parent, which spawns enemies
 #[derive(Component)]
@@ -35,14 +37,21 @@ pub enum EnemyState {
 }
 
 pub(super) fn plugin(app: &mut App) {
-    app.init_resource::<EnemyStrengthRange>()
+    app.add_plugins(archetypes::plugin)
         .add_event::<ShipDestroyed>()
         .add_event::<ShipHit>()
         .add_systems(Startup, spawn_enemy_parent)
         .add_systems(
-            Update,
-            (spawn_enemy, enemy_hit, enemy_lifetime)
+            FixedUpdate,
+            (enemy_hit, enemy_lifetime)
                 .chain()
+                .in_set(AppSet::Update)
+                .run_if(in_state(InGameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            show_damage
+                .in_set(AppSet::Update)
                 .run_if(in_state(InGameState::Playing)),
         )
         .add_systems(OnEnter(InGameState::Preparation), despawn_all_enemies)
@@ -64,22 +73,37 @@ pub struct Enemy {
     pub state: EnemyState,
     /// Colour of the enemy
     pub colour: Color,
-    /// Health of the enemy
-    pub health: u8,
+    /// Health of the enemy, it is destroyed when this reaches zero
+    pub health: f32,
+    /// The health the enemy started with
+    pub max_health: f32,
+    /// Multiplier applied to the score the player gets for this enemy
+    pub score_value: f32,
 }
 
 impl Enemy {
-    /// Create a new enemy
+    /// Create a new enemy with no energy, it will need to charge up before it moves
     pub fn new(lifetime: f32, max_energy: f32, recharge_rate: f32) -> Self {
         Self {
             lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
-            // start with a random amount of energy
-            energy: rand::thread_rng().gen_range(0.0..=max_energy),
+            energy: 0.0,
             max_energy,
             recharge_rate,
             state: EnemyState::Stopped,
             colour: Color::srgb(255.0, 0.0, 0.0), // override later
-            health: 4,
+            health: 4.0,
+            max_health: 4.0,
+            score_value: 1.0,
         }
     }
 }
+
+/// Briefly highlights an enemy's outline after it takes damage.
+#[derive(Component, Debug)]
+pub struct HitFlash(Timer);
+
+impl Default for HitFlash {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.15, TimerMode::Once))
+    }
+}
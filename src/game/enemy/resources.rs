@@ -1,15 +1,14 @@
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
 
 use crate::game::movement::Momentum;
 
-use super::{
-    systems::{calc_strength, randomize},
-    Enemy,
-};
+use super::{systems::randomize, Enemy};
 
-/// The max power of an enemy
-#[derive(Resource)]
+/// The range of stats an enemy can be spawned with
+#[derive(Debug, Clone, Deserialize)]
 pub struct EnemyStrengthRange {
     max_speed: (f32, f32),
     max_energy: (f32, f32),
@@ -36,129 +35,168 @@ impl Default for EnemyStrengthRange {
 }
 
 impl EnemyStrengthRange {
-    /// Get the max power of the enemy
-    pub fn get_power_range(&self) -> (f32, f32) {
-        let min = calc_strength(
-            &Momentum::new(self.max_speed.0, self.mass.0, self.thrust.0),
-            &Enemy::new(self.lifetime.0, self.max_energy.0, self.recharge_rate.0),
-        );
-        let max = calc_strength(
-            &Momentum::new(self.max_speed.1, self.mass.1, self.thrust.1),
-            &Enemy::new(self.lifetime.1, self.max_energy.1, self.recharge_rate.1),
-        );
-        (min, max)
+    /// Scale how fast and how persistent enemies are, keeping their size and lifetime.
+    pub fn scaled(&self, strength: f32) -> Self {
+        let scale = |(min, max): (f32, f32)| (min * strength, max * strength);
+        EnemyStrengthRange {
+            max_speed: scale(self.max_speed),
+            max_energy: scale(self.max_energy),
+            recharge_rate: scale(self.recharge_rate),
+            thrust: scale(self.thrust),
+            ..self.clone()
+        }
     }
     /// Generate stats for a new enemy, based on the max power of the enemy and a bias that reduces each stat by a percentage
-    pub fn get_enemy_stats(&self, bias: f32) -> (Momentum, Enemy) {
+    pub fn get_enemy_stats(&self, bias: f32, rng: &mut impl Rng) -> (Momentum, Enemy) {
         let momentum = Momentum::new(
-            randomize(self.max_speed, bias),
-            randomize(self.mass, bias),
-            randomize(self.thrust, bias),
+            randomize(self.max_speed, bias, rng),
+            randomize(self.mass, bias, rng),
+            randomize(self.thrust, bias, rng),
         );
-        let enemy = Enemy::new(
-            randomize(self.lifetime, bias),
-            randomize(self.max_energy, bias),
-            randomize(self.recharge_rate, bias),
+        let mut enemy = Enemy::new(
+            randomize(self.lifetime, bias, rng),
+            randomize(self.max_energy, bias, rng),
+            randomize(self.recharge_rate, bias, rng),
         );
+        // start with a random amount of energy
+        enemy.energy = rng.gen_range(0.0..=enemy.max_energy);
         (momentum, enemy)
     }
+    /// Check that every range is finite with a positive minimum no larger than
+    /// its maximum, which [`randomize`] relies on
+    pub fn validate(&self) -> Result<(), String> {
+        let ranges = [
+            ("max_speed", self.max_speed),
+            ("max_energy", self.max_energy),
+            ("recharge_rate", self.recharge_rate),
+            ("mass", self.mass),
+            ("thrust", self.thrust),
+            ("lifetime", self.lifetime),
+            ("radius", self.radius),
+        ];
+        for (name, (min, max)) in ranges {
+            if !min.is_finite() || !max.is_finite() || min <= 0.0 || min > max {
+                return Err(format!("{name} range ({min}, {max}) is invalid"));
+            }
+        }
+        Ok(())
+    }
     /// Generate the radius of the new enemy based on the max radius of the enemy, max mass and its current mass
-    pub fn get_radius(&self, mass: f32) -> f32 {
-        randomize(self.radius, mass / self.mass.1).clamp(self.radius.0, self.radius.1)
+    pub fn get_radius(&self, mass: f32, rng: &mut impl Rng) -> f32 {
+        randomize(self.radius, mass / self.mass.1, rng).clamp(self.radius.0, self.radius.1)
     }
-    /// Get the colour of the enemy based on its momentum and energy
-    pub fn get_colour(&self, momentum: &Momentum, enemy: &Enemy) -> Color {
-        let difficulty = calc_strength(momentum, enemy);
-        let (min_difficulty, max_difficulty) = self.get_power_range();
-        let relative_difficulty =
-            (difficulty - min_difficulty) / ((max_difficulty * 2.0) - min_difficulty);
-        // have 10 difficulty colours
-        if relative_difficulty < 0.1 {
-            Color::srgb(255.0, 0.0, 0.0)
-        } else if relative_difficulty < 0.2 {
-            Color::srgb(255.0, 112.5, 0.0)
-        } else if relative_difficulty < 0.3 {
-            Color::srgb(255.0, 255.0, 0.0)
-        } else if relative_difficulty < 0.4 {
-            Color::srgb(112.5, 255.0, 0.0)
-        } else if relative_difficulty < 0.5 {
-            Color::srgb(0.0, 255.0, 0.0)
-        } else if relative_difficulty < 0.6 {
-            Color::srgb(0.0, 255.0, 112.5)
-        } else if relative_difficulty < 0.7 {
-            Color::srgb(0.0, 255.0, 255.0)
-        } else if relative_difficulty < 0.8 {
-            Color::srgb(0.0, 112.5, 255.0)
-        } else if relative_difficulty < 0.9 {
-            Color::srgb(0.0, 0.0, 255.0)
-        } else {
-            Color::srgb(255.0, 255.0, 255.0)
+}
+
+/// The outline an enemy is drawn with
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+pub enum EnemyShape {
+    /// Five-pointed star
+    #[default]
+    Star,
+    /// Arrowhead pointing along the direction of travel
+    Triangle,
+    /// Crescent moon
+    Moon,
+    /// Open U shape
+    UShape,
+}
+
+impl EnemyShape {
+    /// Build the path for this shape at the given radius
+    pub fn path(self, radius: f32) -> Path {
+        match self {
+            EnemyShape::Star => {
+                let num_points = 5;
+                let angle_step = std::f32::consts::PI * 2.0 / num_points as f32;
+                let mut star_points = Vec::with_capacity(num_points * 2);
+
+                for i in 0..num_points * 2 {
+                    let radius_factor = if i % 2 == 0 { 2.0 } else { 1.0 };
+                    let angle = angle_step * (i as f32 / 2.0);
+                    let x = radius * radius_factor * angle.cos();
+                    let y = radius * radius_factor * angle.sin();
+                    star_points.push(Vec2::new(x, y));
+                }
+                GeometryBuilder::build_as(&shapes::Polygon {
+                    points: star_points,
+                    closed: true,
+                })
+            }
+            EnemyShape::Triangle => {
+                let points = vec![
+                    Vec2::new(0.0, radius),                   // tip of the beak
+                    Vec2::new(-(radius * 2.0), radius * 2.0), // start of left wing
+                    Vec2::new(0.0, -radius),                  // tip of the tail
+                    Vec2::new(radius * 2.0, radius * 2.0),    // start of right wing
+                ];
+                GeometryBuilder::build_as(&shapes::Polygon {
+                    points,
+                    closed: true,
+                })
+            }
+            EnemyShape::Moon => {
+                let moon_points = vec![
+                    Vec2::new(0.0, radius),                 // Top point
+                    Vec2::new(radius * 0.5, radius * 0.5),  // Top-right point
+                    Vec2::new(radius * 0.5, -radius * 0.5), // Bottom-right point
+                    Vec2::new(0.0, -radius),                // Bottom point
+                ];
+                GeometryBuilder::build_as(&shapes::Polygon {
+                    points: moon_points,
+                    closed: true,
+                })
+            }
+            EnemyShape::UShape => {
+                let u_points = vec![
+                    Vec2::new(-radius, radius),  // Top-left point
+                    Vec2::new(radius, radius),   // Top-right point
+                    Vec2::new(radius, -radius),  // Bottom-right point
+                    Vec2::new(-radius, -radius), // Bottom-left point
+                ];
+                GeometryBuilder::build_as(&shapes::Polygon {
+                    points: u_points,
+                    closed: false,
+                })
+            }
         }
     }
-    /// Get the shape of the enemy based on its momentum and energy
-    pub fn get_shape(&self, momentum: &Momentum, enemy: &Enemy) -> Path {
-        let difficulty = calc_strength(momentum, enemy);
-        let (min_difficulty, max_difficulty) = self.get_power_range();
-        let relative_difficulty =
-            (difficulty - min_difficulty) / ((max_difficulty * 2.0) - min_difficulty);
-        let radius = self.get_radius(momentum.mass);
-        // have 4 different shapes based on the difficulty level
-        // a circle, a triangle, a square and a pentagon
-        let points = vec![
-            Vec2::new(0.0, radius),                   // tip of the beak
-            Vec2::new(-(radius * 2.0), radius * 2.0), // start of left wing
-            Vec2::new(0.0, -radius),                  // tip of the tail
-            Vec2::new(radius * 2.0, radius * 2.0),    // start of right wing
-        ];
-        let triangle = shapes::Polygon {
-            points,
-            closed: true,
-        };
-        // Five-pointed star
-        let num_points = 5;
-        let angle_step = std::f32::consts::PI * 2.0 / num_points as f32;
-        let mut star_points = Vec::with_capacity(num_points * 2);
+}
 
-        for i in 0..num_points * 2 {
-            let radius_factor = if i % 2 == 0 { 2.0 } else { 1.0 };
-            let angle = angle_step * (i as f32 / 2.0);
-            let x = radius * radius_factor * angle.cos();
-            let y = radius * radius_factor * angle.sin();
-            star_points.push(Vec2::new(x, y));
-        }
-        let star = shapes::Polygon {
-            points: star_points,
-            closed: true,
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::resources::GameRng;
 
-        // Crescent moon
-        let moon_points = vec![
-            Vec2::new(0.0, radius),                 // Top point
-            Vec2::new(radius * 0.5, radius * 0.5),  // Top-right point
-            Vec2::new(radius * 0.5, -radius * 0.5), // Bottom-right point
-            Vec2::new(0.0, -radius),                // Bottom point
-        ];
-        let _moon = shapes::Polygon {
-            points: moon_points,
-            closed: true,
-        };
+    /// The stats of the first few enemies from `rng`, in a form that can be compared.
+    fn first_stats(rng: &mut GameRng) -> Vec<[f32; 7]> {
+        let range = EnemyStrengthRange::default();
+        (0..5)
+            .map(|_| {
+                let (momentum, enemy) = range.get_enemy_stats(0.5, &mut rng.gameplay);
+                [
+                    momentum.max_speed,
+                    momentum.mass,
+                    momentum.thrust,
+                    enemy.lifetime.duration().as_secs_f32(),
+                    enemy.energy,
+                    enemy.max_energy,
+                    enemy.recharge_rate,
+                ]
+            })
+            .collect()
+    }
 
-        // U shape
-        let u_points = vec![
-            Vec2::new(-radius, radius),  // Top-left point
-            Vec2::new(radius, radius),   // Top-right point
-            Vec2::new(radius, -radius),  // Bottom-right point
-            Vec2::new(-radius, -radius), // Bottom-left point
-        ];
-        let _u_shape = shapes::Polygon {
-            points: u_points,
-            closed: false,
-        };
+    #[test]
+    fn the_same_seed_gives_the_same_stats() {
+        let first = first_stats(&mut GameRng::from_seed(42));
+        let second = first_stats(&mut GameRng::from_seed(42));
+        assert_eq!(first, second);
+    }
 
-        if relative_difficulty < 0.5 {
-            GeometryBuilder::build_as(&star)
-        } else {
-            GeometryBuilder::build_as(&triangle)
-        }
+    #[test]
+    fn different_seeds_give_different_stats() {
+        let first = first_stats(&mut GameRng::from_seed(42));
+        let second = first_stats(&mut GameRng::from_seed(43));
+        assert_ne!(first, second);
     }
 }
@@ -1,29 +1,56 @@
-use super::{resources::EnemyStrengthRange, Enemy, EnemyParent, EnemyState};
+use super::{archetypes::EnemyArchetypes, Enemy, EnemyParent, EnemyState, HitFlash};
 use crate::{
     audio::sfx::SfxCommands,
-    game::{movement::Momentum, player::Player},
+    game::{
+        adaptive::AdaptiveDirector,
+        difficulty::Difficulty,
+        flocking::FlockingForce,
+        movement::{momentum_physics, FacesVelocity, Momentum},
+        player::Player,
+        simulation::InterpolatedTransform,
+        steering::Steering,
+    },
     prelude::*,
 };
-use bevy::{utils::hashbrown::Equivalent, window::PrimaryWindow};
+use bevy::{
+    ecs::{system::RunSystemOnce as _, world::Command},
+    window::PrimaryWindow,
+};
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Spawn an enemy at a random location on the map.
+#[derive(Debug, Default)]
+pub struct SpawnEnemy {
+    /// Name of the archetype to spawn, or `None` to pick one by spawn weight.
+    pub archetype: Option<String>,
+}
+
+impl Command for SpawnEnemy {
+    fn apply(self, world: &mut World) {
+        world.run_system_once_with(self, spawn_enemy);
+    }
+}
 
-/// Spawn a enemy at a random location on the map, after a random interval
-pub fn spawn_enemy(
+fn spawn_enemy(
+    In(SpawnEnemy {
+        archetype: archetype_name,
+    }): In<SpawnEnemy>, // required to run this system once
     mut commands: Commands,
     player: Query<&Transform, With<Player>>,
     parent: Query<Entity, With<EnemyParent>>,
-    game_time: ResMut<GameTime>,
-    max_enemy_strength: Res<EnemyStrengthRange>,
-    time: Res<Time>,
+    archetype_handles: Res<ArchetypeHandles>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
     score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    adaptive: Res<AdaptiveDirector>,
+    mut rng: ResMut<GameRng>,
     window: Query<&Window, With<PrimaryWindow>>,
 ) {
-    // only spawn enemies every 5 seconds
-    if game_time.time % 5.0 >= time.delta_seconds() {
+    // the run may have ended between queueing this command and applying it
+    let (Ok(parent), Ok(player_transform)) = (parent.get_single(), player.get_single()) else {
         return;
-    }
-
-    let parent = parent.single();
-    let player_transform = player.single();
+    };
     let Ok(window) = window.get_single() else {
         warn!("no primary window, not spawning enemy");
         return;
@@ -31,25 +58,45 @@ pub fn spawn_enemy(
     let half_width = window.resolution.width() / 2.0;
     let half_height = window.resolution.height() / 2.0;
 
+    // pick what kind of enemy to spawn from the loaded archetypes
+    let Some(archetype) = archetypes
+        .get(&archetype_handles[ArchetypeHandles::PATH_ENEMIES])
+        .and_then(|archetypes| match &archetype_name {
+            Some(name) => archetypes.get(name),
+            None => archetypes.choose(&mut rng.gameplay),
+        })
+    else {
+        warn!("enemy archetype {archetype_name:?} not available, not spawning enemy");
+        return;
+    };
+
     // spawn enemies with different shapes, lifetimes, max speeds, max energies, recharge rates, masses and thrusts.  These are randomized but get more difficult as the game progresses.
-    // The colour and shape of the enemy should be associated with the difficulty level of the spawned enemy's stats.
+    // The colour and shape of the enemy come from its archetype.
     // The enemy should move towards the player, and stop when it runs out of energy.  It should then recharge its energy and start moving again.
     // The enemy should despawn after a certain amount of time, and the player should get points for surviving.
     // The player should lose health if they collide with an enemy.
-    let bias = (score.0 / 100.0).clamp(0.5, 2.0); // the bias is the percentage of the max power of the enemy that the new enemy should have
-    let (momentum, mut enemy) = max_enemy_strength.get_enemy_stats(bias);
+    let settings = difficulty.settings();
+    let bias = settings.bias(score.0) * adaptive.bias_scale; // the bias is the percentage of the max power of the enemy that the new enemy should have
+    let stats = archetype.stats.scaled(settings.enemy_strength);
+    let (momentum, mut enemy) = stats.get_enemy_stats(bias, &mut rng.gameplay);
+    enemy.score_value = archetype.score_value * settings.score_multiplier;
+    enemy.health = archetype.health;
+    enemy.max_health = archetype.health;
 
-    let radius = max_enemy_strength.get_radius(momentum.mass); // radius of the enemy
+    let radius = stats.get_radius(momentum.mass, &mut rng.gameplay); // radius of the enemy
 
     assert!(
         radius < half_width && radius < half_height,
         "Enemy radius is too large"
     );
     // spawn location, must be at least 20% of the window width away from the player and at least its own radius away from a wall.
-    let mut rng = rand::thread_rng();
     let (random_x, random_y) = loop {
-        let random_x = rng.gen_range(-half_width + radius..=half_width - radius);
-        let random_y = rng.gen_range(-half_height + radius..=half_height - radius);
+        let random_x = rng
+            .gameplay
+            .gen_range(-half_width + radius..=half_width - radius);
+        let random_y = rng
+            .gameplay
+            .gen_range(-half_height + radius..=half_height - radius);
         if random_x < player_transform.translation.x - 0.4 * half_width
             || random_x > player_transform.translation.x + 0.4 * half_width
             || random_y < player_transform.translation.y - 0.4 * half_height
@@ -59,27 +106,34 @@ pub fn spawn_enemy(
         }
     };
 
-    // generate a colour and shape based on the difficulty level of the enemy, which is based on the enemy's stats. Red should be the easiest, scaling up to blue the hardest.
-    let colour = max_enemy_strength.get_colour(&momentum, &enemy);
+    let colour = archetype.colour();
     enemy.colour = colour; // set to use for explosion colour on death
-    let path = max_enemy_strength.get_shape(&momentum, &enemy);
-
-    commands.play_sfx(SfxHandles::PATH_ARRIVAL);
-
-    commands
-        .spawn((
-            ShapeBundle {
-                path,
-                spatial: SpatialBundle {
-                    transform: Transform::from_xyz(random_x, random_y, 0.0),
-                    ..default()
-                },
+    let path = archetype.shape.path(radius);
+
+    commands.play_sfx_at(SfxHandles::KEY_ENEMY_ARRIVE, Vec2::new(random_x, random_y));
+
+    let mut enemy_entity = commands.spawn((
+        ShapeBundle {
+            path,
+            spatial: SpatialBundle {
+                transform: Transform::from_xyz(random_x, random_y, 0.0),
                 ..default()
             },
-            Fill::color(colour),
-            Stroke::new(Color::BLACK, 1.0),
-            Name::new(format!("Enemy {}", rand::random::<u16>())),
-            momentum,
+            ..default()
+        },
+        Fill::color(colour),
+        Stroke::new(Color::BLACK, 1.0),
+        Name::new(format!(
+            "Enemy {} {}",
+            archetype.name,
+            rng.cosmetic.gen::<u16>()
+        )),
+        momentum,
+        archetype.behaviour,
+        Steering::random(&mut rng.gameplay),
+        // enemies are drawn nose down
+        FacesVelocity { offset: PI },
+        (
             Collider::ball(radius),
             ColliderMassProperties::Density(0.2),
             Restitution::new(0.9),
@@ -87,13 +141,19 @@ pub fn spawn_enemy(
             Ccd::enabled(),
             GravityScale(0.0),
             Velocity {
-                linvel: Vec2::new(0.1, 0.1),
-                angvel: 2.0,
+                linvel: Vec2::new(6.0, 6.0),
+                angvel: 0.0,
             },
+            momentum_physics(),
             ActiveEvents::COLLISION_EVENTS,
-            enemy,
-        ))
-        .set_parent(parent);
+            InterpolatedTransform::default(),
+        ),
+        enemy,
+    ));
+    enemy_entity.set_parent(parent);
+    if archetype.flocking.is_active() {
+        enemy_entity.insert((archetype.flocking, FlockingForce::default()));
+    }
 }
 
 /// Remove enemies from the map when their lifetime is up, and give the player score
@@ -106,6 +166,9 @@ pub fn enemy_lifetime(
 ) {
     let time_passed = time.delta().as_secs_f32();
     for (transform, entity, mut enemy, momentum) in enemies.iter_mut() {
+        if enemy.health <= 0.0 {
+            continue; // already destroyed by a collision
+        }
         match enemy.state {
             EnemyState::Stopped => {
                 enemy.energy += (enemy.recharge_rate * time_passed).clamp(0.0, enemy.max_energy);
@@ -122,8 +185,11 @@ pub fn enemy_lifetime(
                     return;
                 };
                 if enemy.lifetime.finished() {
-                    score.0 += calc_strength(momentum, &enemy);
-                    commands.play_sfx(SfxHandles::PATH_TAP);
+                    score.0 += calc_strength(momentum, &enemy) * enemy.score_value;
+                    commands.play_sfx_at(
+                        SfxHandles::KEY_ENEMY_EXPIRE,
+                        transform.translation.truncate(),
+                    );
                     commands.entity(entity).remove_parent().despawn();
                     info!("Survied! Adding score");
                     ship_destroyed_events.send(ShipDestroyed {
@@ -137,25 +203,79 @@ pub fn enemy_lifetime(
     }
 }
 
-/// Enemies lose energy when they get hit
+/// Destroying an enemy is worth this many times the score for outliving it
+const KILL_SCORE_MULTIPLIER: f32 = 2.0;
+
+/// Enemies lose energy and health when they get hit, and are destroyed when
+/// their health runs out
 pub fn enemy_hit(
     mut commands: Commands,
-    mut objects: Query<(Entity, &mut Enemy, &Velocity)>,
+    mut objects: Query<(&mut Enemy, &Velocity, &Momentum)>,
     mut hit_events: EventReader<ShipHit>,
+    mut score: ResMut<Score>,
+    mut ship_destroyed_events: EventWriter<ShipDestroyed>,
 ) {
-    for events in hit_events.read() {
-        for (entity, mut enemy, velocity) in objects.iter_mut() {
-            // read events and reduce energy of enemies.
-            if events.id.equivalent(&entity) {
-                let velocity_magnitude = get_magnitude(velocity);
-                commands.add_trauma(velocity_magnitude);
-                info!(
-                    "enemy {} hit something at {} with {}/{} energy",
-                    events.id, velocity_magnitude, enemy.energy, enemy.max_energy
-                );
-                enemy.energy -= enemy.max_energy * (velocity_magnitude); // drop energy by %, based on velocity, on collision
+    for event in hit_events.read() {
+        // read events and reduce energy and health of enemies.
+        let Ok((mut enemy, velocity, momentum)) = objects.get_mut(event.id) else {
+            continue;
+        };
+        if enemy.health <= 0.0 {
+            continue; // already destroyed this tick
+        }
+        let velocity_magnitude = get_magnitude(velocity);
+        commands.add_trauma(velocity_magnitude);
+        info!(
+            "enemy {} hit something at {} with {}/{} energy, taking {} damage",
+            event.id, velocity_magnitude, enemy.energy, enemy.max_energy, event.damage
+        );
+        enemy.energy -= enemy.max_energy * (velocity_magnitude); // drop energy by %, based on velocity, on collision
+        if event.damage <= 0.0 {
+            continue;
+        }
+        enemy.health -= event.damage;
+        commands.entity(event.id).insert(HitFlash::default());
+        if enemy.health <= 0.0 {
+            score.0 += calc_strength(momentum, &enemy) * enemy.score_value * KILL_SCORE_MULTIPLIER;
+            commands.play_sfx_at(SfxHandles::KEY_ENEMY_DESTROYED, Vec2::new(event.x, event.y));
+            commands.entity(event.id).remove_parent().despawn();
+            info!("Destroyed an enemy! Adding score");
+            ship_destroyed_events.send(ShipDestroyed {
+                x: event.x,
+                y: event.y,
+                colour: event.colour,
+            });
+        }
+    }
+}
+
+/// Show how worn down each enemy is through its outline, which gets brighter
+/// and thicker as it loses health and flashes white when it is hit
+pub fn show_damage(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut enemies: Query<(Entity, &Enemy, &mut Stroke, Option<&mut HitFlash>)>,
+) {
+    for (entity, enemy, mut stroke, flash) in enemies.iter_mut() {
+        let mut flashing = false;
+        if let Some(mut flash) = flash {
+            if flash.0.tick(time.delta()).finished() {
+                commands.entity(entity).remove::<HitFlash>();
+            } else {
+                flashing = true;
             }
         }
+        let (colour, width) = if flashing {
+            (Color::WHITE, 3.0)
+        } else {
+            let damage = 1.0 - (enemy.health / enemy.max_health).clamp(0.0, 1.0);
+            (Color::srgb(damage, 0.4 * damage, 0.0), 1.0 + 2.0 * damage)
+        };
+        // a changed stroke re-tessellates the whole shape, so only touch it
+        // when it actually looks different
+        if stroke.color != colour || stroke.options.line_width != width {
+            *stroke = Stroke::new(colour, width);
+        }
     }
 }
 
@@ -165,12 +285,12 @@ pub fn calc_strength(momentum: &Momentum, enemy: &Enemy) -> f32 {
 }
 
 /// Generate a random number between min and (max * bias)
-pub fn randomize(range: (f32, f32), bias: f32) -> f32 {
+pub fn randomize(range: (f32, f32), bias: f32, rng: &mut impl Rng) -> f32 {
     let (min, max) = range;
     assert!(min > 0.0, "min must be greater than 0");
     assert!(bias > 0.0, "bias must be greater than 0");
     let max = (max * bias).clamp(min, f32::INFINITY);
     assert!(max > 0.0, "max must be greater than 0");
     assert!(min <= max, "min must be less than or equal to max * bias");
-    rand::thread_rng().gen_range(min..=max)
+    rng.gen_range(min..=max)
 }
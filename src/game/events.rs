@@ -1,3 +1,4 @@
+use super::options::GameOptions;
 use crate::prelude::*;
 
 /// Event for requesting a restart of the game.
@@ -20,9 +21,9 @@ pub struct EndGameTriggered;
 #[derive(Event, Default)]
 pub struct OptionsRequested;
 
-/// Event for updating game options.
-#[derive(Event, Default)]
-pub struct UpdateOptions;
+/// Event for updating game options. Carries the complete new options.
+#[derive(Event, Debug, Clone)]
+pub struct UpdateOptions(pub GameOptions);
 
 /// Event for when a ship is destroyed, with the x and y coordinates of the ship.
 #[derive(Event, Default)]
@@ -37,8 +38,24 @@ pub struct ShipDestroyed {
 #[derive(Event)]
 pub struct ShipHit {
     pub id: Entity,
-    // pub damage: f32,
-    // pub x: f32,
-    // pub y: f32,
-    // pub colour: Color,
+    pub damage: f32,
+    pub x: f32,
+    pub y: f32,
+    pub colour: Color,
+}
+
+/// Event for when the wave director starts a new wave.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveStarted {
+    /// The wave number, starting from 1.
+    pub number: u32,
+    /// How many enemies will spawn during the wave.
+    pub count: u32,
+}
+
+/// Event for when every enemy of a wave has been spawned and is gone.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveCleared {
+    /// The wave number, starting from 1.
+    pub number: u32,
 }
@@ -0,0 +1,100 @@
+//! Boids style flocking for enemies.
+//!
+//! Without this, enemies all take the same line towards the player and only
+//! spread out by crashing into each other. Enemies with a [`Flocking`]
+//! component also steer away from neighbours that are too close, line up with
+//! their neighbours' heading and drift towards the middle of their group.
+//! The weights come from the enemy's archetype, so one kind of enemy can hunt
+//! in packs while another spreads out.
+
+use super::{enemy::Enemy, movement::chase_movement};
+use crate::prelude::*;
+use serde::Deserialize;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Flocking>()
+        .register_type::<FlockingForce>()
+        .add_systems(
+            FixedUpdate,
+            flocking_forces
+                .before(chase_movement)
+                .in_set(AppSet::Update)
+                .run_if(in_state(InGameState::Playing)),
+        );
+}
+
+/// How strongly an enemy reacts to the enemies around it. All weights default
+/// to zero, which turns flocking off.
+#[derive(Component, Debug, Clone, Copy, Deserialize, Reflect)]
+#[reflect(Component)]
+#[serde(default)]
+pub struct Flocking {
+    /// Keep away from neighbours that are too close
+    pub separation: f32,
+    /// Fly the same way as the neighbours
+    pub alignment: f32,
+    /// Move towards the middle of the neighbours
+    pub cohesion: f32,
+    /// Only enemies closer than this, in pixels, count as neighbours
+    pub radius: f32,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Flocking {
+            separation: 0.0,
+            alignment: 0.0,
+            cohesion: 0.0,
+            radius: 100.0,
+        }
+    }
+}
+
+impl Flocking {
+    /// Whether any of the weights are set.
+    pub fn is_active(&self) -> bool {
+        self.separation != 0.0 || self.alignment != 0.0 || self.cohesion != 0.0
+    }
+}
+
+/// The combined flocking direction for this tick, added to the enemy's
+/// steering direction before thrust is applied.
+#[derive(Component, Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct FlockingForce(pub Vec2);
+
+/// Work out each flocking enemy's separation, alignment and cohesion from the
+/// enemies around it.
+fn flocking_forces(
+    mut flockers: Query<(Entity, &Transform, &Flocking, &mut FlockingForce)>,
+    neighbours: Query<(Entity, &Transform, &Velocity), With<Enemy>>,
+) {
+    for (entity, transform, flocking, mut force) in flockers.iter_mut() {
+        let position = transform.translation.truncate();
+        let mut separation = Vec2::ZERO;
+        let mut heading = Vec2::ZERO;
+        let mut centre = Vec2::ZERO;
+        let mut count = 0.0;
+        for (other, other_transform, other_velocity) in neighbours.iter() {
+            let offset = position - other_transform.translation.truncate();
+            let distance = offset.length();
+            if other == entity || distance >= flocking.radius {
+                continue;
+            }
+            // push away harder the closer the neighbour is
+            separation += offset.normalize_or_zero() * (1.0 - distance / flocking.radius);
+            heading += other_velocity.linvel.normalize_or_zero();
+            centre += other_transform.translation.truncate();
+            count += 1.0;
+        }
+        if count == 0.0 {
+            force.0 = Vec2::ZERO;
+            continue;
+        }
+        let alignment = (heading / count).normalize_or_zero();
+        let cohesion = (centre / count - position).normalize_or_zero();
+        force.0 = separation.clamp_length_max(1.0) * flocking.separation
+            + alignment * flocking.alignment
+            + cohesion * flocking.cohesion;
+    }
+}
@@ -0,0 +1,146 @@
+//! The high score table, saved between sessions.
+//!
+//! There is a separate table for every game mode and difficulty, so runs are
+//! only ranked against runs that played the same way.
+
+use super::difficulty::DifficultyLevel;
+use crate::{persistence::Persistence, prelude::*};
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GameMode>()
+        .init_resource::<HighScores>()
+        .add_systems(
+            Update,
+            save_high_scores.run_if(resource_changed::<HighScores>),
+        );
+}
+
+/// How many entries each table keeps.
+pub const HIGH_SCORE_SLOTS: usize = 10;
+
+/// The key the table is saved under.
+const STORAGE_KEY: &str = "high_scores";
+
+/// The rules a run is played under. Each mode has its own high score tables.
+#[derive(
+    Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize,
+)]
+pub enum GameMode {
+    /// Survive the waves for as long as possible
+    #[default]
+    Arcade,
+}
+
+impl GameMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Arcade => "Arcade",
+        }
+    }
+}
+
+/// One finished run in the table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    /// The player's initials
+    pub name: String,
+    pub score: u32,
+    /// How long the run lasted, in seconds
+    pub runtime: f32,
+    /// The seed the run was played with, so it can be replayed
+    pub seed: u64,
+    /// When the run ended, in seconds since the unix epoch
+    pub timestamp: u64,
+}
+
+/// The ranked entries for one mode and difficulty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScoreTable {
+    mode: GameMode,
+    difficulty: DifficultyLevel,
+    /// Best first, never longer than [`HIGH_SCORE_SLOTS`]
+    entries: Vec<HighScoreEntry>,
+}
+
+/// Every high score table.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    tables: Vec<ScoreTable>,
+}
+
+impl FromWorld for HighScores {
+    fn from_world(world: &mut World) -> Self {
+        world
+            .resource::<Persistence>()
+            .load_json(STORAGE_KEY)
+            .unwrap_or_default()
+    }
+}
+
+impl HighScores {
+    /// The entries for a mode and difficulty, best first.
+    pub fn entries(&self, mode: GameMode, difficulty: DifficultyLevel) -> &[HighScoreEntry] {
+        self.tables
+            .iter()
+            .find(|table| table.mode == mode && table.difficulty == difficulty)
+            .map_or(&[], |table| &table.entries)
+    }
+
+    /// The best score for a mode and difficulty, or zero if there isn't one yet.
+    pub fn best(&self, mode: GameMode, difficulty: DifficultyLevel) -> u32 {
+        self.entries(mode, difficulty)
+            .first()
+            .map_or(0, |entry| entry.score)
+    }
+
+    /// Whether a score is good enough to get into the table.
+    pub fn qualifies(&self, mode: GameMode, difficulty: DifficultyLevel, score: u32) -> bool {
+        let entries = self.entries(mode, difficulty);
+        score > 0
+            && (entries.len() < HIGH_SCORE_SLOTS
+                || entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Add an entry to the table, returning its rank starting from 0, or `None`
+    /// if it didn't make the table.
+    pub fn insert(
+        &mut self,
+        mode: GameMode,
+        difficulty: DifficultyLevel,
+        entry: HighScoreEntry,
+    ) -> Option<usize> {
+        if !self.qualifies(mode, difficulty, entry.score) {
+            return None;
+        }
+        let index = match self
+            .tables
+            .iter()
+            .position(|table| table.mode == mode && table.difficulty == difficulty)
+        {
+            Some(index) => index,
+            None => {
+                self.tables.push(ScoreTable {
+                    mode,
+                    difficulty,
+                    entries: Vec::new(),
+                });
+                self.tables.len() - 1
+            }
+        };
+        let entries = &mut self.tables[index].entries;
+        // later runs go below earlier runs with the same score
+        let rank = entries.partition_point(|existing| existing.score >= entry.score);
+        entries.insert(rank, entry);
+        entries.truncate(HIGH_SCORE_SLOTS);
+        Some(rank)
+    }
+}
+
+fn save_high_scores(persistence: Res<Persistence>, high_scores: Res<HighScores>) {
+    // nothing new to save when the table has only just been loaded
+    if high_scores.is_added() {
+        return;
+    }
+    persistence.save_json(STORAGE_KEY, &*high_scores);
+}
@@ -1,6 +1,6 @@
 //! Spawn the main level.
 
-use super::player::SpawnPlayer;
+use super::{difficulty::Difficulty, player::SpawnPlayer};
 use crate::prelude::*;
 use bevy::ecs::world::Command;
 
@@ -17,10 +17,13 @@ impl Command for SpawnLevel {
     fn apply(self, world: &mut World) {
         // The only thing we have in our level is a player,
         // but add things like walls etc. here.
+        let settings = world.resource::<Difficulty>().settings();
         world.commands().add(SpawnPlayer {
-            max_speed: 8.0,
-            mass: 1.3,
-            thrust: 15.0,
+            max_speed: settings.player_max_speed,
+            mass: settings.player_mass,
+            thrust: settings.player_thrust,
+            hull: settings.hull,
+            lives: settings.lives,
         });
 
         // Flush the commands we just added so that they are
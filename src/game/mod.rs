@@ -3,16 +3,24 @@
 //! Feel free to change the logic found here if you feel like tinkering around
 //! to get a feeling for the template.
 
+pub mod adaptive;
 mod collisions;
-mod enemy;
+pub mod difficulty;
+pub mod enemy;
 pub mod events;
+pub mod flocking;
+pub mod high_scores;
 pub mod level;
-mod movement;
+pub mod movement;
+pub mod options;
 mod particles;
-mod player;
+pub mod player;
 pub mod resources;
+pub mod simulation;
 pub mod state;
+pub mod steering;
 mod walls;
+pub mod waves;
 
 use crate::prelude::*;
 
@@ -22,29 +30,34 @@ pub(super) fn plugin(app: &mut App) {
 
     app.init_resource::<Score>();
 
+    // Seeded randomness, reseeded at the start of every run
+    app.insert_resource(RunSeed::from_env())
+        .init_resource::<GameRng>();
+
     // Game time system
     app.init_resource::<GameTime>().add_systems(
-        Update,
+        FixedUpdate,
         (update_game_time.in_set(AppSet::TickTimers)).run_if(in_state(InGameState::Playing)),
     );
 
-    // Physics system
-    app.add_plugins((
-        RapierPhysicsPlugin::<NoUserData>::default(),
-        // #[cfg(debug_assertions)]
-        // RapierDebugRenderPlugin::default(),
-    ));
-
     // Game systems
     app.add_plugins((
+        simulation::plugin,
+        difficulty::plugin,
+        high_scores::plugin,
+        options::plugin,
+        adaptive::plugin,
         movement::plugin,
         player::plugin,
         level::plugin,
         enemy::plugin,
+        flocking::plugin,
         walls::plugin,
         particles::plugin,
         collisions::plugin,
         state::plugin,
+        steering::plugin,
+        waves::plugin,
     ));
 }
 
@@ -1,23 +1,51 @@
 //! This module contains systems for moving objects in the game world.
 //! Most movement in this game is with momentum and friction.
+//!
+//! Ships never move themselves directly: they push on their rigid body with an
+//! [`ExternalForce`] and Rapier integrates the result, so collisions and
+//! restitution always agree with how the ship is flying.
 
 use super::{
     enemy::{Enemy, EnemyState},
+    flocking::FlockingForce,
+    options::{ControlScheme, FlightModel, GameOptions},
     player::Player,
+    steering::{Steering, SteeringBehaviour, SteeringInput},
+};
+use crate::{
+    input::{ActiveControls, ThrustInput},
+    prelude::*,
 };
-use crate::prelude::*;
 use bevy::window::PrimaryWindow;
 use std::f32::consts::PI;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
-        Update,
-        (player_movement, chase_movement, bounded_movement)
+        FixedUpdate,
+        (
+            player_movement,
+            chase_movement,
+            limit_speed,
+            face_velocity,
+            bounded_movement,
+        )
             .chain() // run these systems in sequence
+            .in_set(AppSet::Update)
             .run_if(in_state(InGameState::Playing)),
     );
 }
 
+/// `Momentum` stats were tuned as pixels per tick at this rate, so they are
+/// scaled by it to get the per-second values Rapier works in.
+pub const MOMENTUM_TICK_HZ: f32 = 60.0;
+
+/// Linear damping applied to every ship, so a coasting ship slowly bleeds speed.
+pub const SHIP_LINEAR_DAMPING: f32 = 0.1;
+
+/// The fastest a rotate-and-thrust ship can turn, in radians per second, however
+/// slowly it is moving.
+pub const MAX_TURN_RATE: f32 = 6.0;
+
 #[derive(Component)]
 pub struct BoundedMovement;
 
@@ -56,64 +84,154 @@ impl Momentum {
             thrust,
         }
     }
+
+    /// The top speed in pixels per second, in any direction.
+    pub fn top_speed(&self) -> f32 {
+        self.max_speed * MOMENTUM_TICK_HZ
+    }
+
+    /// The acceleration at full thrust in pixels per second squared.
+    pub fn acceleration(&self) -> f32 {
+        self.thrust / self.mass * MOMENTUM_TICK_HZ
+    }
+
+    /// How fast a rotate-and-thrust ship can turn at `speed`, in radians per second.
+    /// Turning takes the same acceleration as thrusting, so a slow ship turns
+    /// tightly while a fast one swings wide.
+    pub fn turn_rate(&self, speed: f32) -> f32 {
+        (self.acceleration() / speed.max(f32::EPSILON)).min(MAX_TURN_RATE)
+    }
+
+    /// The force needed to accelerate a rigid body of `body_mass` along `direction`.
+    /// Directions longer than one are clamped, so diagonal thrust is no stronger
+    /// than straight thrust.
+    pub fn thrust_force(&self, direction: Vec2, body_mass: f32) -> Vec2 {
+        direction.clamp_length_max(1.0) * self.acceleration() * body_mass
+    }
+}
+
+/// Physics components every ship needs for [`Momentum`] to drive it.
+pub fn momentum_physics() -> impl Bundle {
+    (
+        ExternalForce::default(),
+        Damping {
+            linear_damping: SHIP_LINEAR_DAMPING,
+            angular_damping: 0.0,
+        },
+        ReadMassProperties::default(),
+    )
 }
 
 #[derive(Component)]
 pub struct KeyboardMovement;
 
-/// This system changes the velocity vector of objects with Momentum based on player input. Pressing a move direction key increases the velocity in that direction based on the mass and thrust of the object. Releasing the key decreases the velocity in that direction based on the friction and mass of the object.
+/// Turns the object to face the way it is moving.
+/// The offset is the angle between the object's nose and its local up direction.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct FacesVelocity {
+    pub offset: f32,
+}
+
+/// Which way a rotate-and-thrust ship is pointing, in radians anticlockwise
+/// from up. Ships with a heading face it instead of their velocity.
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Heading(pub f32);
+
+/// This system sets the thrust of objects with Momentum based on player input. Holding a thrust action pushes the object in that direction based on the mass and thrust of the object, and pushing the stick part way gives part thrust.
+///
+/// With the [`FlightModel::RotateAndThrust`] flight model, left and right turn
+/// the ship's [`Heading`] instead, and up and down thrust forwards and backwards
+/// along it. The pointer and joystick always steer directly.
 pub fn player_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut objects: Query<(&mut Transform, &Momentum, &mut Velocity), With<Player>>,
+    mut commands: Commands,
+    thrust: Res<ThrustInput>,
+    options: Res<GameOptions>,
+    controls: Res<ActiveControls>,
     time: Res<Time>,
+    mut objects: Query<
+        (
+            Entity,
+            &Transform,
+            &Velocity,
+            &Momentum,
+            &ReadMassProperties,
+            &mut ExternalForce,
+            Option<&mut Heading>,
+        ),
+        With<Player>,
+    >,
 ) {
-    for (mut transform, momentum, mut velocity) in objects.iter_mut() {
-        let mut acceleration = Vec2::ZERO;
-        if keyboard_input.pressed(KeyCode::KeyW) || keyboard_input.pressed(KeyCode::ArrowUp) {
-            acceleration.y += momentum.thrust / momentum.mass;
-        }
-        if keyboard_input.pressed(KeyCode::KeyS) || keyboard_input.pressed(KeyCode::ArrowDown) {
-            acceleration.y -= momentum.thrust / momentum.mass;
-        }
-        if keyboard_input.pressed(KeyCode::KeyA) || keyboard_input.pressed(KeyCode::ArrowLeft) {
-            acceleration.x -= momentum.thrust / momentum.mass;
-        }
-        if keyboard_input.pressed(KeyCode::KeyD) || keyboard_input.pressed(KeyCode::ArrowRight) {
-            acceleration.x += momentum.thrust / momentum.mass;
+    let rotate_and_thrust = options.flight_model == FlightModel::RotateAndThrust
+        && controls.0 == ControlScheme::Keyboard;
+    for (entity, transform, velocity, momentum, mass_properties, mut force, heading) in
+        objects.iter_mut()
+    {
+        let body_mass = mass_properties.get().mass;
+        if !rotate_and_thrust {
+            if heading.is_some() {
+                commands.entity(entity).remove::<Heading>();
+            }
+            force.force = momentum.thrust_force(**thrust, body_mass);
+            continue;
         }
-        velocity.linvel += acceleration * time.delta_seconds();
-        velocity.linvel = velocity.linvel.clamp(
-            Vec2::splat(-momentum.max_speed),
-            Vec2::splat(momentum.max_speed),
+
+        // pick up from wherever the ship was facing when the model was switched on
+        let mut angle = heading.as_deref().map_or_else(
+            || transform.rotation.to_euler(EulerRot::ZYX).0,
+            |heading| heading.0,
         );
-        transform.translation += velocity.linvel.extend(0.0);
-        // rotate the object to face the direction of movement assuming that the object is facing up to begin with
-        let angle = velocity.linvel.angle_between(Vec2::Y);
-        transform.rotation = Quat::from_rotation_z(-angle);
+        let turn_rate = momentum.turn_rate(velocity.linvel.length());
+        angle -= thrust.x * turn_rate * time.delta_seconds();
+        let forward = Vec2::from_angle(angle).rotate(Vec2::Y);
+        force.force = momentum.thrust_force(forward * thrust.y, body_mass);
+
+        match heading {
+            Some(mut heading) => heading.0 = angle,
+            None => {
+                commands.entity(entity).insert(Heading(angle));
+            }
+        }
     }
 }
 
-/// This system changes the velocity vector of objects with Chaser based on the position of the target object. The object will move towards the closest target object at a speed determined by the mass and thrust of the object.
+/// This system sets the thrust of enemies based on the closest target object. Each enemy's [`SteeringBehaviour`] picks the direction, and the enemy accelerates that way at a rate determined by the mass and thrust of the object.
 pub fn chase_movement(
-    mut commands: Commands,
     mut objects: Query<(
-        Entity,
         &Transform,
+        &Velocity,
         &Enemy,
         &Momentum,
-        &mut Velocity,
+        &SteeringBehaviour,
+        &mut Steering,
+        &ReadMassProperties,
+        &mut ExternalForce,
         &mut Fill,
+        Option<&FlockingForce>,
     )>,
-    target: Query<&Transform, With<Player>>,
+    target: Query<(&Transform, &Velocity), With<Player>>,
     time: Res<Time>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for (entity, transform, enemy, momentum, mut velocity, mut fill) in objects.iter_mut() {
+    for (
+        transform,
+        velocity,
+        enemy,
+        momentum,
+        behaviour,
+        mut steering,
+        mass_properties,
+        mut force,
+        mut fill,
+        flocking,
+    ) in objects.iter_mut()
+    {
         match enemy.state {
             EnemyState::Moving => {
                 fill.color = enemy.colour;
                 // find the closest target
-                let target_transform = target.iter().fold(None, |closest, target| {
-                    let distance = transform.translation.distance(target.translation);
+                let closest_target = target.iter().fold(None, |closest, target| {
+                    let distance = transform.translation.distance(target.0.translation);
                     closest.map_or(Some((distance, target)), |(closest_distance, _)| {
                         if distance < closest_distance {
                             Some((distance, target))
@@ -122,34 +240,67 @@ pub fn chase_movement(
                         }
                     })
                 });
-                // accelerate towards the target
-                let Some((_dist, target_transform)) = target_transform else {
+                let Some((_dist, (target_transform, target_velocity))) = closest_target else {
                     warn!("no target found for chaser movement");
                     return;
                 };
-                let direction =
-                    target_transform.translation.truncate() - transform.translation.truncate();
-                let acceleration = direction.normalize() * momentum.thrust / momentum.mass;
-
-                velocity.linvel += acceleration * time.delta_seconds();
-                velocity.linvel = velocity.linvel.clamp(
-                    Vec2::splat(-momentum.max_speed),
-                    Vec2::splat(momentum.max_speed),
+                // accelerate the way the steering behaviour wants to go
+                let input = SteeringInput {
+                    position: transform.translation.truncate(),
+                    velocity: velocity.linvel,
+                    top_speed: momentum.top_speed(),
+                    target_position: target_transform.translation.truncate(),
+                    target_velocity: target_velocity.linvel,
+                };
+                let mut direction = behaviour.direction(
+                    &input,
+                    &mut steering,
+                    time.delta_seconds(),
+                    &mut rng.gameplay,
                 );
+                if let Some(flocking) = flocking {
+                    direction += flocking.0;
+                }
+                force.force = momentum.thrust_force(direction, mass_properties.get().mass);
             }
             EnemyState::Stopped => {
                 // gray out the enemy to indicate that it is inactive
                 fill.color = Color::srgb_u8(100, 100, 100);
+                force.force = Vec2::ZERO;
             }
         }
-        let new_translation = transform.translation + velocity.linvel.extend(0.0);
-        // rotate the object to face the direction of movement assuming that the object is facing up to begin with
-        let angle = velocity.linvel.angle_between(Vec2::Y);
-        let new_rotation = Quat::from_rotation_z(-angle + PI);
-        commands.entity(entity).insert(Transform {
-            translation: new_translation,
-            rotation: new_rotation,
-            ..*transform
-        });
+    }
+}
+
+/// This system caps the speed of objects with Momentum at their top speed, whichever direction they are moving in.
+pub fn limit_speed(mut objects: Query<(&Momentum, &mut Velocity)>) {
+    for (momentum, mut velocity) in objects.iter_mut() {
+        let top_speed = momentum.top_speed();
+        if velocity.linvel.length_squared() > top_speed * top_speed {
+            velocity.linvel = velocity.linvel.clamp_length_max(top_speed);
+        }
+    }
+}
+
+/// This system spins objects so that they end the next tick facing their direction of movement,
+/// or their [`Heading`] if they have one.
+pub fn face_velocity(
+    mut objects: Query<(&Transform, &FacesVelocity, Option<&Heading>, &mut Velocity)>,
+    time: Res<Time>,
+) {
+    for (transform, facing, heading, mut velocity) in objects.iter_mut() {
+        let direction = match heading {
+            Some(heading) => heading.0,
+            None if velocity.linvel.length_squared() < f32::EPSILON => {
+                velocity.angvel = 0.0;
+                continue;
+            }
+            // objects are facing up to begin with
+            None => Vec2::Y.angle_between(velocity.linvel),
+        };
+        let target = direction + facing.offset;
+        let (current, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+        let turn = (target - current + PI).rem_euclid(2.0 * PI) - PI;
+        velocity.angvel = turn / time.delta_seconds();
     }
 }
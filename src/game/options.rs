@@ -0,0 +1,203 @@
+//! Player options, saved between sessions.
+//!
+//! The options screen never changes [`GameOptions`] directly. It sends an
+//! [`UpdateOptions`] event with the new options, which are then applied to the
+//! window, camera and audio mix and saved.
+
+use crate::{persistence::Persistence, prelude::*};
+use bevy::window::{PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GameOptions>()
+        .add_systems(Update, update_options)
+        .add_systems(
+            Update,
+            (apply_window_mode, apply_screen_shake)
+                .after(update_options)
+                .run_if(resource_changed::<GameOptions>),
+        );
+}
+
+/// The key the options are saved under.
+const STORAGE_KEY: &str = "options";
+
+/// How far the camera shakes at full screen shake strength.
+pub const SHAKE_AMPLITUDE: f32 = 8.0;
+
+/// How the player steers their ship.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// Keys or a gamepad once they have been used, the pointer or virtual
+    /// joystick until then
+    #[default]
+    Auto,
+    /// Keys or a gamepad
+    Keyboard,
+    /// Thrust towards the mouse cursor or touch point
+    Pointer,
+    /// Drag an on-screen joystick, for touch screens
+    Joystick,
+}
+
+impl ControlScheme {
+    /// Every scheme, in the order they are cycled through.
+    pub const ALL: [ControlScheme; 4] = [
+        ControlScheme::Auto,
+        ControlScheme::Keyboard,
+        ControlScheme::Pointer,
+        ControlScheme::Joystick,
+    ];
+
+    /// The scheme after this one, wrapping back round to the first.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|scheme| *scheme == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ControlScheme::Auto => "Auto",
+            ControlScheme::Keyboard => "Keys / Pad",
+            ControlScheme::Pointer => "Pointer",
+            ControlScheme::Joystick => "Joystick",
+        }
+    }
+}
+
+/// How the player's thrust moves the ship when steering with keys or a gamepad.
+/// It only applies while [`ControlScheme::Keyboard`] is in use, either chosen or
+/// picked by [`ControlScheme::Auto`] once a key or button is pressed. The
+/// pointer and joystick always fly directly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum FlightModel {
+    /// Thrust straight along the direction pressed
+    #[default]
+    Direct,
+    /// Left and right turn the ship, up thrusts the way it is pointing
+    RotateAndThrust,
+}
+
+impl FlightModel {
+    pub fn next(self) -> Self {
+        match self {
+            FlightModel::Direct => FlightModel::RotateAndThrust,
+            FlightModel::RotateAndThrust => FlightModel::Direct,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FlightModel::Direct => "Direct",
+            FlightModel::RotateAndThrust => "Rotate",
+        }
+    }
+}
+
+/// Everything the player can change on the options screen.
+/// Volumes and strengths go from 0 (off) to 1 (full), apart from screen shake
+/// which can be turned up past 1.
+#[derive(Resource, Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct GameOptions {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+    pub screen_shake: f32,
+    /// Scales how many particles explosions spawn
+    pub particle_density: f32,
+    pub fullscreen: bool,
+    pub control_scheme: ControlScheme,
+    pub flight_model: FlightModel,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        GameOptions {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            effects_volume: 1.0,
+            screen_shake: 1.0,
+            particle_density: 1.0,
+            fullscreen: false,
+            control_scheme: ControlScheme::default(),
+            flight_model: FlightModel::default(),
+        }
+    }
+}
+
+impl FromWorld for GameOptions {
+    fn from_world(world: &mut World) -> Self {
+        world
+            .resource::<Persistence>()
+            .load_json(STORAGE_KEY)
+            .unwrap_or_default()
+    }
+}
+
+/// Take the newest requested options, then save them.
+fn update_options(
+    mut updates: EventReader<UpdateOptions>,
+    mut options: ResMut<GameOptions>,
+    persistence: Res<Persistence>,
+) {
+    let Some(UpdateOptions(new_options)) = updates.read().last() else {
+        return;
+    };
+    if *options != *new_options {
+        *options = new_options.clone();
+        persistence.save_json(STORAGE_KEY, &*options);
+    }
+}
+
+fn apply_window_mode(
+    options: Res<GameOptions>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let mode = if options.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+    for mut window in windows.iter_mut() {
+        if window.mode != mode {
+            window.mode = mode;
+        }
+    }
+}
+
+fn apply_screen_shake(options: Res<GameOptions>, mut cameras: Query<&mut ShakeSettings>) {
+    for mut settings in cameras.iter_mut() {
+        settings.amplitude = SHAKE_AMPLITUDE * options.screen_shake;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_schemes_cycle_through_every_scheme_and_wrap() {
+        let mut scheme = ControlScheme::default();
+        for expected in ControlScheme::ALL
+            .iter()
+            .cycle()
+            .skip(1)
+            .take(ControlScheme::ALL.len())
+        {
+            scheme = scheme.next();
+            assert_eq!(scheme, *expected);
+        }
+        assert_eq!(scheme, ControlScheme::default());
+    }
+
+    #[test]
+    fn flight_models_toggle() {
+        assert_eq!(FlightModel::Direct.next(), FlightModel::RotateAndThrust);
+        assert_eq!(FlightModel::RotateAndThrust.next(), FlightModel::Direct);
+    }
+}
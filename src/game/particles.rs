@@ -2,6 +2,7 @@
 //!
 //! When the enemies are destroyed, a particle system is spawned at their location.
 
+use super::options::GameOptions;
 use crate::prelude::*;
 use bevy_particle_systems::{
     ColorOverTime, Curve, CurvePoint, JitteredValue, ParticleBurst, ParticleSystem,
@@ -14,19 +15,26 @@ pub(super) fn plugin(app: &mut App) {
 }
 
 /// Spawn a particle system when a ship is destroyed.
+/// The number of particles is scaled by the particle density option.
 fn destroyed_ship(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    options: Res<GameOptions>,
     mut destroyed_ship_events: EventReader<ShipDestroyed>,
 ) {
+    let density = options.particle_density;
+    if density <= 0.0 {
+        destroyed_ship_events.clear();
+        return;
+    }
     for event in destroyed_ship_events.read() {
         commands
             .spawn(ParticleSystemBundle {
                 transform: Transform::from_xyz(event.x, event.y, 0.0),
                 particle_system: ParticleSystem {
-                    max_particles: 500,
+                    max_particles: (500.0 * density) as usize,
                     texture: asset_server.load("particles/px.png").into(),
-                    spawn_rate_per_second: 1000.0.into(),
+                    spawn_rate_per_second: (1000.0 * density).into(),
                     initial_speed: JitteredValue::jittered(200.0, -50.0..50.0),
                     velocity_modifiers: vec![Drag(0.01.into())],
                     lifetime: JitteredValue::jittered(4.0, -2.0..2.0),
@@ -39,7 +47,7 @@ fn destroyed_ship(
                     system_duration_seconds: 0.1,
                     max_distance: Some(50.0),
                     scale: 2.0.into(),
-                    bursts: vec![ParticleBurst::new(0.0, 100)],
+                    bursts: vec![ParticleBurst::new(0.0, (100.0 * density) as usize)],
                     ..default()
                 },
                 ..default()
@@ -2,16 +2,28 @@
 //! Note that this is separate from the `movement` module as that could be used
 //! for other characters as well.
 
-use super::movement::{KeyboardMovement, Momentum};
-use crate::prelude::*;
+use super::{
+    movement::{momentum_physics, FacesVelocity, KeyboardMovement, Momentum},
+    simulation::InterpolatedTransform,
+};
+use crate::{audio::sfx::SfxCommands, prelude::*};
 use bevy::{
     color::palettes::css::SILVER,
     ecs::{system::RunSystemOnce as _, world::Command},
+    utils::HashSet,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<Player>(); // used for debug inspection
+    app.register_type::<PlayerHealth>();
     app.add_systems(OnEnter(InGameState::Preparation), despawn_player);
+    app.add_systems(
+        FixedUpdate,
+        (player_hit, invulnerability)
+            .chain()
+            .in_set(AppSet::Update)
+            .run_if(in_state(InGameState::Playing)),
+    );
 }
 
 #[derive(Component, Default, Reflect)]
@@ -22,6 +34,39 @@ pub struct SpawnPlayer {
     pub max_speed: f32,
     pub mass: f32,
     pub thrust: f32,
+    /// Damage the ship can take before a life is lost
+    pub hull: f32,
+    /// Extra lives on top of the first one
+    pub lives: u32,
+}
+
+/// How much damage the player can still take.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PlayerHealth {
+    /// Damage left before a life is lost
+    pub hull: f32,
+    /// The hull the ship starts every life with
+    pub max_hull: f32,
+    /// Extra lives left, the run ends when the hull runs out with none left
+    pub lives: u32,
+}
+
+/// The player can't take damage while this is ticking, and blinks to show it.
+#[derive(Component, Debug)]
+pub struct Invulnerable(Timer);
+
+impl Invulnerable {
+    /// Grace period after taking a hit.
+    const AFTER_HIT: f32 = 1.0;
+    /// Grace period after losing a life.
+    const AFTER_RESPAWN: f32 = 3.0;
+    /// How many times per second the ship blinks.
+    const BLINK_HZ: f32 = 8.0;
+
+    fn new(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Once))
+    }
 }
 
 impl Command for SpawnPlayer {
@@ -36,10 +81,13 @@ fn spawn_player(
         max_speed,
         mass,
         thrust,
+        hull,
+        lives,
     }): In<SpawnPlayer>, // required to run this system once
     mut commands: Commands,
 ) {
     let start_pos = Vec3::new(0.0, 0.0, 0.0);
+    let momentum = Momentum::new(max_speed, mass, thrust);
     commands.spawn((
         ShapeBundle {
             path: player_shape(),
@@ -49,23 +97,105 @@ fn spawn_player(
         Fill::color(SILVER),
         Stroke::new(Color::BLACK, 1.0),
         Name::new("Player"),
-        Momentum::new(max_speed, mass, thrust),
+        momentum,
         KeyboardMovement,
-        Collider::ball(7.5),
-        ColliderMassProperties::Density(0.6),
-        Restitution::new(0.9),
-        RigidBody::Dynamic,
-        Ccd::enabled(),
-        GravityScale(0.0),
-        Velocity {
-            linvel: Vec2::new(0.0, max_speed * 0.1),
-            angvel: 0.0,
+        FacesVelocity::default(),
+        (
+            Collider::ball(7.5),
+            ColliderMassProperties::Density(0.6),
+            Restitution::new(0.9),
+            RigidBody::Dynamic,
+            Ccd::enabled(),
+            GravityScale(0.0),
+            Velocity {
+                linvel: Vec2::new(0.0, momentum.top_speed() * 0.1),
+                angvel: 0.0,
+            },
+            momentum_physics(),
+            ActiveEvents::all(),
+            InterpolatedTransform::default(),
+        ),
+        PlayerHealth {
+            hull,
+            max_hull: hull,
+            lives,
         },
         Player,
-        ActiveEvents::all(),
     ));
 }
 
+/// Take damage when hit, lose a life when the hull runs out, and end the run
+/// when there are no lives left. Only the first hit on a ship each tick lands,
+/// as the [`Invulnerable`] it earns isn't inserted until the commands run.
+fn player_hit(
+    mut commands: Commands,
+    mut players: Query<(&mut PlayerHealth, Has<Invulnerable>)>,
+    mut hit_events: EventReader<ShipHit>,
+    mut ship_destroyed_events: EventWriter<ShipDestroyed>,
+    mut end_game_events: EventWriter<EndGameTriggered>,
+) {
+    let mut hit_this_tick = HashSet::new();
+    for event in hit_events.read() {
+        let Ok((mut health, invulnerable)) = players.get_mut(event.id) else {
+            continue;
+        };
+        if invulnerable || health.hull <= 0.0 || !hit_this_tick.insert(event.id) {
+            continue;
+        }
+        health.hull -= event.damage;
+        info!(
+            "Player took {} damage, {}/{} hull left",
+            event.damage, health.hull, health.max_hull
+        );
+        commands.add_trauma(0.5);
+        commands.play_sfx(SfxHandles::KEY_PLAYER_HIT);
+        if health.hull > 0.0 {
+            commands
+                .entity(event.id)
+                .insert(Invulnerable::new(Invulnerable::AFTER_HIT));
+            continue;
+        }
+        ship_destroyed_events.send(ShipDestroyed {
+            x: event.x,
+            y: event.y,
+            colour: event.colour,
+        });
+        if health.lives == 0 {
+            info!("Player destroyed");
+            end_game_events.send(EndGameTriggered);
+            continue;
+        }
+        health.lives -= 1;
+        health.hull = health.max_hull;
+        info!("Player lost a life, {} left", health.lives);
+        commands
+            .entity(event.id)
+            .insert(Invulnerable::new(Invulnerable::AFTER_RESPAWN));
+    }
+}
+
+/// Blink the player while invulnerable, and make them vulnerable again once
+/// the grace period is over.
+fn invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut players: Query<(Entity, &mut Invulnerable, &mut Fill), With<Player>>,
+) {
+    for (entity, mut invulnerable, mut fill) in players.iter_mut() {
+        if invulnerable.0.tick(time.delta()).finished() {
+            fill.color = SILVER.into();
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+        let blink = (invulnerable.0.elapsed_secs() * Invulnerable::BLINK_HZ) as u32 % 2 == 0;
+        fill.color = if blink {
+            SILVER.with_alpha(0.2).into()
+        } else {
+            SILVER.into()
+        };
+    }
+}
+
 /// Despawn the player character when the game ends.
 fn despawn_player(mut commands: Commands, query: Query<Entity, With<Player>>) {
     for entity in query.iter() {
@@ -90,3 +220,60 @@ fn player_shape() -> Path {
         closed: true,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce as _;
+
+    fn hit(id: Entity, damage: f32) -> ShipHit {
+        ShipHit {
+            id,
+            damage,
+            x: 0.0,
+            y: 0.0,
+            colour: Color::WHITE,
+        }
+    }
+
+    fn world_with_player(hull: f32, lives: u32) -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<Events<ShipHit>>();
+        world.init_resource::<Events<ShipDestroyed>>();
+        world.init_resource::<Events<EndGameTriggered>>();
+        let player = world
+            .spawn(PlayerHealth {
+                hull,
+                max_hull: hull,
+                lives,
+            })
+            .id();
+        (world, player)
+    }
+
+    #[test]
+    fn only_the_first_hit_in_a_tick_lands() {
+        let (mut world, player) = world_with_player(10.0, 1);
+        world.send_event(hit(player, 4.0));
+        world.send_event(hit(player, 4.0));
+        world.run_system_once(player_hit);
+
+        let health = world.get::<PlayerHealth>(player).unwrap();
+        assert_eq!(health.hull, 6.0);
+        assert_eq!(health.lives, 1);
+        assert!(world.get::<Invulnerable>(player).is_some());
+    }
+
+    #[test]
+    fn a_lost_life_starts_on_a_full_hull() {
+        let (mut world, player) = world_with_player(4.0, 1);
+        world.send_event(hit(player, 4.0));
+        world.send_event(hit(player, 4.0));
+        world.run_system_once(player_hit);
+
+        let health = world.get::<PlayerHealth>(player).unwrap();
+        assert_eq!(health.hull, 4.0);
+        assert_eq!(health.lives, 0);
+        assert_eq!(world.resource::<Events<EndGameTriggered>>().len(), 0);
+    }
+}
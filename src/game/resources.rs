@@ -8,6 +8,7 @@
 //! Resources are also automatically synchronized across threads when using Bevy's parallel systems.
 
 use crate::prelude::*;
+use rand::{rngs::StdRng, SeedableRng as _};
 
 #[derive(Resource)]
 pub struct Score(pub f32);
@@ -29,3 +30,61 @@ impl Default for GameTime {
         GameTime { time: 0.0 }
     }
 }
+
+/// Seed used for the next run. Leave as `None` to pick a fresh random seed
+/// every time the game enters [`InGameState::Preparation`].
+///
+/// On native builds this can be set with the `CHASE_ME_SEED` environment
+/// variable to replay a run from a bug report.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct RunSeed(pub Option<u64>);
+
+impl RunSeed {
+    /// Read the seed override from the environment, if one was given.
+    pub fn from_env() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(seed) = std::env::var("CHASE_ME_SEED")
+            .ok()
+            .and_then(|seed| seed.trim().parse().ok())
+        {
+            return Self(Some(seed));
+        }
+        Self(None)
+    }
+}
+
+/// Seeded random number generators for a single run.
+///
+/// Randomness that affects gameplay (enemy stats, spawn positions, colours)
+/// must be drawn from `gameplay`, while cosmetic and audio randomness uses
+/// `cosmetic`. Keeping the streams apart means that playing an extra sound
+/// effect can never change what the next enemy looks like.
+#[derive(Resource)]
+pub struct GameRng {
+    /// The seed both streams were created from.
+    pub seed: u64,
+    /// Stream for everything that affects the simulation.
+    pub gameplay: StdRng,
+    /// Stream for particles, audio variations and menu flavour text.
+    pub cosmetic: StdRng,
+}
+
+impl GameRng {
+    /// Mixed into the seed so the cosmetic stream differs from the gameplay one.
+    const COSMETIC_STREAM: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    /// Create both random streams from a single seed.
+    pub fn from_seed(seed: u64) -> Self {
+        GameRng {
+            seed,
+            gameplay: StdRng::seed_from_u64(seed),
+            cosmetic: StdRng::seed_from_u64(seed ^ Self::COSMETIC_STREAM),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::from_seed(rand::random())
+    }
+}
@@ -0,0 +1,113 @@
+//! Fixed-rate simulation.
+//!
+//! Movement, enemy energy and collisions run in [`FixedUpdate`] so that the
+//! game plays the same regardless of the rendered frame rate. Rapier steps in
+//! the same schedule. Between ticks, the rendered position of physics objects
+//! is interpolated so motion still looks smooth on high refresh rate screens.
+
+use crate::prelude::*;
+use bevy::transform::TransformSystem;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SimulationSettings>()
+        .add_plugins((
+            RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule(),
+            // #[cfg(debug_assertions)]
+            // RapierDebugRenderPlugin::default(),
+        ))
+        .add_systems(
+            Update,
+            apply_tick_rate.run_if(resource_changed::<SimulationSettings>),
+        )
+        .add_systems(OnEnter(InGameState::Playing), resume_physics)
+        .add_systems(OnExit(InGameState::Playing), pause_physics)
+        .add_systems(FixedFirst, restore_simulated_transform)
+        .add_systems(FixedLast, record_simulated_transform)
+        .add_systems(
+            PostUpdate,
+            interpolate_transform.before(TransformSystem::TransformPropagate),
+        );
+}
+
+/// Settings for the fixed-rate simulation.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SimulationSettings {
+    /// How many simulation ticks run per second.
+    pub tick_rate: f64,
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        SimulationSettings { tick_rate: 60.0 }
+    }
+}
+
+/// Keep the fixed timestep and the physics step in sync with the configured tick rate.
+fn apply_tick_rate(
+    settings: Res<SimulationSettings>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    fixed_time.set_timestep_hz(settings.tick_rate);
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: fixed_time.timestep().as_secs_f32(),
+        substeps: 1,
+    };
+    info!(
+        "Simulation running at {} ticks per second",
+        settings.tick_rate
+    );
+}
+
+/// Only step the physics while the game is being played, so nothing drifts while paused.
+fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+fn pause_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+/// Add this to a physics object to smooth its rendered movement between ticks.
+///
+/// The simulated transform is stored here, and the entity's [`Transform`] is
+/// only an interpolated copy of it outside of the fixed schedule.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct InterpolatedTransform {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+}
+
+/// Put the simulated transform back before a tick, so the simulation never sees
+/// the interpolated one.
+fn restore_simulated_transform(mut objects: Query<(&mut Transform, &InterpolatedTransform)>) {
+    for (mut transform, interpolated) in objects.iter_mut() {
+        if let Some(current) = interpolated.current {
+            transform.translation = current.translation;
+            transform.rotation = current.rotation;
+        }
+    }
+}
+
+/// Remember the result of the latest tick.
+fn record_simulated_transform(mut objects: Query<(&Transform, &mut InterpolatedTransform)>) {
+    for (transform, mut interpolated) in objects.iter_mut() {
+        interpolated.previous = interpolated.current.or(Some(*transform));
+        interpolated.current = Some(*transform);
+    }
+}
+
+/// Blend between the last two ticks based on how far we are into the next one.
+fn interpolate_transform(
+    fixed_time: Res<Time<Fixed>>,
+    mut objects: Query<(&mut Transform, &InterpolatedTransform)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, interpolated) in objects.iter_mut() {
+        let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) else {
+            continue;
+        };
+        transform.translation = previous.translation.lerp(current.translation, alpha);
+        transform.rotation = previous.rotation.slerp(current.rotation, alpha);
+    }
+}
@@ -18,8 +18,8 @@ pub(super) fn plugin(app: &mut App) {
         .add_event::<RestartRequested>()
         .add_event::<TogglePauseRequested>()
         .add_event::<EndGameTriggered>()
-        // .add_event::<OptionsRequested>()
-        // .add_event::<UpdateOptions>()
+        .add_event::<OptionsRequested>()
+        .add_event::<UpdateOptions>()
         .add_systems(
             Update,
             check_summary_condition
@@ -30,10 +30,19 @@ pub(super) fn plugin(app: &mut App) {
 }
 
 /// Clean up the game state when the game is over
-fn clean_up(mut commands: Commands, mut next_state: ResMut<NextState<InGameState>>) {
+fn clean_up(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<InGameState>>,
+    run_seed: Res<RunSeed>,
+) {
     next_state.set(InGameState::None);
     commands.insert_resource(Score::default());
     commands.insert_resource(GameTime::default());
+
+    // Reseed so that the same seed always plays out the same way.
+    let seed = run_seed.0.unwrap_or_else(rand::random);
+    info!("Starting run with seed {seed}");
+    commands.insert_resource(GameRng::from_seed(seed));
 }
 
 pub fn check_summary_condition(
@@ -45,7 +54,7 @@ pub fn check_summary_condition(
     if end_game_events.is_empty() {
         return;
     }
-    commands.play_sfx(SfxHandles::PATH_CRASH);
+    commands.play_sfx(SfxHandles::KEY_PLAYER_CRASH);
     end_game_events.clear();
     next_state.set(InGameState::Summary);
     next_screen.set(Screen::Summary);
@@ -0,0 +1,122 @@
+//! Steering behaviours decide which way an enemy wants to thrust.
+//!
+//! A behaviour only picks a direction from the enemy's and its target's
+//! position and velocity. `chase_movement` then pushes the enemy that way at
+//! full thrust, so every behaviour is held to the same [`Momentum`] limits and
+//! still overshoots when the target turns sharply.
+//!
+//! [`Momentum`]: super::movement::Momentum
+
+use crate::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use std::f32::consts::PI;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<SteeringBehaviour>()
+        .register_type::<Steering>();
+}
+
+/// How long ahead, in seconds, an interceptor is willing to aim
+const MAX_LEAD_SECONDS: f32 = 1.5;
+/// The slowest closing speed an interceptor assumes, in pixels per second, so a
+/// ship that is stopped or flying away still leads its target a little
+const MIN_CLOSING_SPEED: f32 = 50.0;
+/// How far to the side of the target a flanker aims when it is far away, in pixels
+const FLANK_OFFSET: f32 = 150.0;
+/// Distance at which a flanker starts closing in on the target directly, in pixels
+const FLANK_RANGE: f32 = 300.0;
+/// How quickly a wanderer's heading drifts, in radians per second
+const WANDER_TURN_RATE: f32 = 3.0;
+/// Distance at which a wanderer notices the target and homes in, in pixels
+const WANDER_HOMING_RANGE: f32 = 200.0;
+
+/// How an enemy steers towards its target.
+#[derive(Component, Debug, Default, Clone, Copy, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum SteeringBehaviour {
+    /// Accelerate straight at where the target is now
+    #[default]
+    #[serde(alias = "Chase")]
+    Pursue,
+    /// Aim at where the target is going to be, based on its velocity
+    Intercept,
+    /// Swing wide and come at the target from the side
+    Flank,
+    /// Drift around aimlessly, only homing in when the target gets close
+    Wander,
+}
+
+/// Per enemy state used by the steering behaviours.
+#[derive(Component, Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Steering {
+    /// Which side a flanker goes round the target, 1 for anticlockwise, -1 for clockwise
+    pub side: f32,
+    /// The heading a wanderer is drifting along, in radians
+    pub wander_heading: f32,
+}
+
+impl Steering {
+    /// Pick a random flanking side and starting wander heading.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            side: if rng.gen_bool(0.5) { 1.0 } else { -1.0 },
+            wander_heading: rng.gen_range(-PI..PI),
+        }
+    }
+}
+
+/// Where a steering enemy and its target are, and how they are moving.
+#[derive(Debug, Clone, Copy)]
+pub struct SteeringInput {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// The top speed of the steering enemy, in pixels per second
+    pub top_speed: f32,
+    pub target_position: Vec2,
+    pub target_velocity: Vec2,
+}
+
+impl SteeringBehaviour {
+    /// The direction the enemy should thrust in, with a length of at most one.
+    pub fn direction(
+        self,
+        input: &SteeringInput,
+        steering: &mut Steering,
+        delta_seconds: f32,
+        rng: &mut impl Rng,
+    ) -> Vec2 {
+        let to_target = input.target_position - input.position;
+        let distance = to_target.length();
+        match self {
+            SteeringBehaviour::Pursue => to_target.normalize_or_zero(),
+            SteeringBehaviour::Intercept => {
+                // estimate how long it takes to get there, the faster we are
+                // already closing the sooner we arrive
+                let closing_speed = input.velocity.dot(to_target.normalize_or_zero());
+                let speed =
+                    closing_speed.clamp(MIN_CLOSING_SPEED, input.top_speed.max(MIN_CLOSING_SPEED));
+                let lead = (distance / speed).min(MAX_LEAD_SECONDS);
+                let predicted = input.target_position + input.target_velocity * lead;
+                (predicted - input.position).normalize_or_zero()
+            }
+            SteeringBehaviour::Flank => {
+                // aim beside the target, moving the aim point in as we close
+                let offset = FLANK_OFFSET * (distance / FLANK_RANGE).min(1.0);
+                let side = to_target.perp().normalize_or_zero() * steering.side;
+                (to_target + side * offset).normalize_or_zero()
+            }
+            SteeringBehaviour::Wander => {
+                steering.wander_heading +=
+                    rng.gen_range(-WANDER_TURN_RATE..=WANDER_TURN_RATE) * delta_seconds;
+                let wander = Vec2::from_angle(steering.wander_heading);
+                if distance < WANDER_HOMING_RANGE {
+                    (wander + to_target.normalize_or_zero() * 2.0).normalize_or_zero()
+                } else {
+                    wander
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! The wave director decides when enemies spawn.
+//!
+//! A run is a schedule of [`Wave`]s. Each wave spawns a number of enemies, one
+//! at a time with a gap between them, never letting more than a set number be
+//! alive at once. Once every enemy of a wave has spawned and none are left, the
+//! wave is cleared and there is a breather before the next one starts. After
+//! the last scheduled wave, the final wave repeats with one extra enemy each time.
+
+use std::time::Duration;
+
+use super::{
+    adaptive::AdaptiveDirector,
+    difficulty::{Difficulty, DifficultySettings},
+    enemy::{archetypes::EnemyArchetypes, Enemy, SpawnEnemy},
+};
+use crate::{audio::sfx::SfxCommands, prelude::*};
+use rand::{distributions::WeightedIndex, prelude::Distribution as _};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<WaveDirector>()
+        .add_event::<WaveStarted>()
+        .add_event::<WaveCleared>()
+        .add_systems(
+            FixedUpdate,
+            run_waves
+                .in_set(AppSet::Update)
+                .run_if(in_state(InGameState::Playing)),
+        )
+        .add_systems(Update, (play_wave_sfx, check_wave_mixes))
+        .add_systems(OnEnter(InGameState::Preparation), reset_waves);
+}
+
+/// A single wave of enemies.
+#[derive(Debug, Clone)]
+pub struct Wave {
+    /// How many enemies spawn during the wave
+    pub count: u32,
+    /// Archetype names and their relative weights. Leave empty to use each
+    /// archetype's own spawn weight.
+    pub mix: Vec<(String, f32)>,
+    /// Seconds between spawns
+    pub spacing: f32,
+    /// Seconds of calm after the wave is cleared, before the next one starts
+    pub breather: f32,
+    /// The most enemies that can be alive at once, spawning waits for a free slot
+    pub max_concurrent: usize,
+}
+
+impl Wave {
+    fn new(count: u32, spacing: f32, breather: f32, max_concurrent: usize) -> Self {
+        Self {
+            count,
+            mix: Vec::new(),
+            spacing,
+            breather,
+            max_concurrent,
+        }
+    }
+
+    /// Only spawn the given archetypes, in the given proportions.
+    fn with_mix(mut self, mix: &[(&str, f32)]) -> Self {
+        self.mix = mix
+            .iter()
+            .map(|(name, weight)| (name.to_string(), *weight))
+            .collect();
+        self
+    }
+
+    /// Apply the difficulty's wave size and spawn pacing to this wave, with the
+    /// pacing further scaled by `interval_scale`.
+    fn scaled(mut self, settings: &DifficultySettings, interval_scale: f32) -> Self {
+        self.count = ((self.count as f32 * settings.wave_size).round() as u32).max(1);
+        self.spacing *= settings.spawn_interval * interval_scale;
+        self.breather *= settings.spawn_interval * interval_scale;
+        self
+    }
+
+    /// Pick the archetype of the next enemy from the mix, leaving out any the
+    /// `archetypes` can't spawn. `None` means any archetype will do.
+    fn pick_archetype(&self, archetypes: &EnemyArchetypes, rng: &mut GameRng) -> Option<String> {
+        let weights = self.mix.iter().map(|(name, weight)| {
+            if archetypes.get(name).is_some() {
+                *weight
+            } else {
+                0.0
+            }
+        });
+        let index = WeightedIndex::new(weights).ok()?.sample(&mut rng.gameplay);
+        self.mix.get(index).map(|(name, _)| name.clone())
+    }
+}
+
+/// What the director is currently doing.
+#[derive(Debug)]
+enum WavePhase {
+    /// Waiting for the next wave to start
+    Breather(Timer),
+    /// Spawning the enemies of the current wave
+    Spawning { remaining: u32, cooldown: Timer },
+    /// Everything has spawned, waiting for the last enemies to go
+    Clearing,
+}
+
+/// Runs the schedule of waves for the current run.
+#[derive(Resource, Debug)]
+pub struct WaveDirector {
+    /// The waves to play through, in order
+    pub waves: Vec<Wave>,
+    /// Seconds before the first wave starts
+    pub start_delay: f32,
+    /// The current wave number, 0 before the first wave starts
+    number: u32,
+    phase: WavePhase,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        let start_delay = 2.0;
+        WaveDirector {
+            waves: vec![
+                Wave::new(3, 4.0, 3.0, 3).with_mix(&[("drifter", 1.0)]),
+                Wave::new(4, 3.5, 3.0, 3).with_mix(&[("drifter", 3.0), ("crescent", 1.0)]),
+                Wave::new(5, 3.0, 4.0, 4),
+                Wave::new(6, 3.0, 4.0, 4).with_mix(&[("hunter", 2.0), ("crescent", 1.0)]),
+                Wave::new(8, 2.5, 5.0, 5),
+            ],
+            start_delay,
+            number: 0,
+            phase: WavePhase::Breather(Timer::from_seconds(start_delay, TimerMode::Once)),
+        }
+    }
+}
+
+impl WaveDirector {
+    /// The current wave number, 0 before the first wave starts.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// The wave with the given number, starting from 1.
+    pub fn wave(&self, number: u32) -> Wave {
+        let index = number.saturating_sub(1) as usize;
+        match self.waves.get(index) {
+            Some(wave) => wave.clone(),
+            None => {
+                let mut wave = self.waves.last().cloned().unwrap_or_else(|| {
+                    warn!("wave director has no waves scheduled, using a default wave");
+                    Wave::new(5, 3.0, 4.0, 4)
+                });
+                wave.count += (index + 1 - self.waves.len().max(1)) as u32;
+                wave
+            }
+        }
+    }
+
+    /// Go back to before the first wave, keeping the schedule.
+    pub fn reset(&mut self) {
+        self.number = 0;
+        self.phase = WavePhase::Breather(Timer::from_seconds(self.start_delay, TimerMode::Once));
+    }
+
+    /// Move the schedule on by `delta` with `alive` enemies on the field.
+    /// `spawn` is called for each enemy that is due, and the enemy only counts
+    /// towards the wave if it returns true.
+    fn advance(
+        &mut self,
+        delta: Duration,
+        alive: usize,
+        settings: &DifficultySettings,
+        interval_scale: f32,
+        mut spawn: impl FnMut(&Wave) -> bool,
+    ) -> Option<WaveProgress> {
+        let number = self.number;
+        let wave = self.wave(number).scaled(settings, interval_scale);
+
+        match &mut self.phase {
+            WavePhase::Breather(timer) => {
+                if timer.tick(delta).finished() {
+                    let number = number + 1;
+                    let wave = self.wave(number).scaled(settings, interval_scale);
+                    self.number = number;
+                    self.phase = WavePhase::Spawning {
+                        remaining: wave.count,
+                        // the first enemy of a wave spawns straight away
+                        cooldown: Timer::default(),
+                    };
+                    return Some(WaveProgress::Started(WaveStarted {
+                        number,
+                        count: wave.count,
+                    }));
+                }
+            }
+            WavePhase::Spawning {
+                remaining,
+                cooldown,
+            } => {
+                // the cooldown keeps counting while the wave is at its cap, so a
+                // slot freeing up is filled straight away
+                if cooldown.tick(delta).finished() && alive < wave.max_concurrent && spawn(&wave) {
+                    *remaining = remaining.saturating_sub(1);
+                    *cooldown = Timer::from_seconds(wave.spacing, TimerMode::Once);
+                }
+                if *remaining == 0 {
+                    self.phase = WavePhase::Clearing;
+                }
+            }
+            WavePhase::Clearing => {
+                if alive == 0 {
+                    self.phase =
+                        WavePhase::Breather(Timer::from_seconds(wave.breather, TimerMode::Once));
+                    return Some(WaveProgress::Cleared(WaveCleared { number }));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A wave starting or ending, as reported by [`WaveDirector::advance`].
+#[derive(Debug)]
+enum WaveProgress {
+    Started(WaveStarted),
+    Cleared(WaveCleared),
+}
+
+fn reset_waves(mut director: ResMut<WaveDirector>) {
+    director.reset();
+}
+
+/// Advance the wave schedule and spawn enemies when they are due.
+fn run_waves(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut director: ResMut<WaveDirector>,
+    difficulty: Res<Difficulty>,
+    adaptive: Res<AdaptiveDirector>,
+    mut rng: ResMut<GameRng>,
+    archetype_handles: Res<ArchetypeHandles>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
+    enemies: Query<(), With<Enemy>>,
+    mut started_events: EventWriter<WaveStarted>,
+    mut cleared_events: EventWriter<WaveCleared>,
+) {
+    let alive = enemies.iter().count();
+    let settings = difficulty.settings();
+    let archetypes = archetypes.get(&archetype_handles[ArchetypeHandles::PATH_ENEMIES]);
+    let progress = director.advance(
+        time.delta(),
+        alive,
+        &settings,
+        adaptive.interval_scale,
+        |wave| {
+            // an enemy that can't spawn doesn't count, the next tick tries again
+            let Some(archetypes) = archetypes.filter(|archetypes| archetypes.any_spawnable())
+            else {
+                return false;
+            };
+            commands.add(SpawnEnemy {
+                archetype: wave.pick_archetype(archetypes, &mut rng),
+            });
+            true
+        },
+    );
+
+    match progress {
+        Some(WaveProgress::Started(started)) => {
+            info!(
+                "Wave {} started with {} enemies",
+                started.number, started.count
+            );
+            started_events.send(started);
+        }
+        Some(WaveProgress::Cleared(cleared)) => {
+            info!("Wave {} cleared", cleared.number);
+            cleared_events.send(cleared);
+        }
+        None => {}
+    }
+}
+
+/// Report wave mixes that name archetypes the data file doesn't have, whenever
+/// it is (re)loaded. Those entries are left out when picking what to spawn.
+fn check_wave_mixes(
+    mut events: EventReader<AssetEvent<EnemyArchetypes>>,
+    assets: Res<Assets<EnemyArchetypes>>,
+    director: Res<WaveDirector>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        let Some(archetypes) = assets.get(*id) else {
+            continue;
+        };
+        for (index, wave) in director.waves.iter().enumerate() {
+            for (name, _) in &wave.mix {
+                if archetypes.get(name).is_none() {
+                    error!(
+                        "Wave {} mix names enemy archetype '{name}', which is missing or invalid",
+                        index + 1
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Announce the start and end of each wave.
+fn play_wave_sfx(
+    mut commands: Commands,
+    mut started_events: EventReader<WaveStarted>,
+    mut cleared_events: EventReader<WaveCleared>,
+) {
+    for _ in started_events.read() {
+        commands.play_sfx(SfxHandles::KEY_WAVE_START);
+    }
+    for _ in cleared_events.read() {
+        commands.play_sfx(SfxHandles::KEY_WAVE_CLEAR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Long enough for any timer in these tests to run out.
+    const LONG: Duration = Duration::from_secs(100);
+
+    fn one_wave_director(max_concurrent: usize) -> (WaveDirector, DifficultySettings) {
+        let mut director = WaveDirector {
+            waves: vec![Wave::new(3, 1.0, 1.0, max_concurrent)],
+            start_delay: 1.0,
+            ..default()
+        };
+        director.reset();
+        (director, Difficulty::default().settings())
+    }
+
+    #[test]
+    fn a_wave_starts_spawns_clears_and_breathes() {
+        let (mut director, settings) = one_wave_director(10);
+        let count = director.wave(1).scaled(&settings, 1.0).count;
+
+        let started = director.advance(LONG, 0, &settings, 1.0, |_| true);
+        assert!(matches!(
+            started,
+            Some(WaveProgress::Started(WaveStarted { number: 1, count: started_count })) if started_count == count
+        ));
+
+        let mut spawned = 0;
+        for _ in 0..count {
+            assert!(matches!(director.phase, WavePhase::Spawning { .. }));
+            director.advance(LONG, 0, &settings, 1.0, |_| {
+                spawned += 1;
+                true
+            });
+        }
+        assert_eq!(spawned, count);
+        assert!(matches!(director.phase, WavePhase::Clearing));
+
+        // not cleared while an enemy is still around
+        assert!(director
+            .advance(LONG, 1, &settings, 1.0, |_| true)
+            .is_none());
+        let cleared = director.advance(LONG, 0, &settings, 1.0, |_| true);
+        assert!(matches!(
+            cleared,
+            Some(WaveProgress::Cleared(WaveCleared { number: 1 }))
+        ));
+        assert!(matches!(director.phase, WavePhase::Breather(_)));
+    }
+
+    #[test]
+    fn failed_spawns_do_not_count() {
+        let (mut director, settings) = one_wave_director(10);
+        let count = director.wave(1).scaled(&settings, 1.0).count;
+        director.advance(LONG, 0, &settings, 1.0, |_| true);
+
+        for _ in 0..count + 2 {
+            director.advance(LONG, 0, &settings, 1.0, |_| false);
+        }
+        assert!(matches!(
+            director.phase,
+            WavePhase::Spawning { remaining, .. } if remaining == count
+        ));
+    }
+
+    #[test]
+    fn a_full_wave_waits_for_a_free_slot() {
+        let (mut director, settings) = one_wave_director(1);
+        director.advance(LONG, 0, &settings, 1.0, |_| true);
+
+        director.advance(LONG, 1, &settings, 1.0, |_| panic!("spawned over the cap"));
+        let mut spawned = false;
+        director.advance(Duration::ZERO, 0, &settings, 1.0, |_| {
+            spawned = true;
+            true
+        });
+        assert!(spawned);
+    }
+}
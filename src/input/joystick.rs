@@ -0,0 +1,196 @@
+//! An on-screen joystick for touch screens. Dragging the knob away from the
+//! middle thrusts that way, further for more thrust. It can be dragged with the
+//! mouse too.
+
+use bevy::{ui::Val::*, window::PrimaryWindow};
+
+use super::{scale_past_dead_zone, update_actions, ActionSystem, ActiveControls, ThrustInput};
+use crate::{
+    game::options::ControlScheme,
+    prelude::*,
+    theme::palette::{BUTTON_PRESSED_BACKGROUND, NODE_BACKGROUND},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Playing), spawn_joystick)
+        .add_systems(
+            PreUpdate,
+            joystick_thrust
+                .in_set(ActionSystem)
+                .after(update_actions)
+                .run_if(in_state(InGameState::Playing).and_then(joystick_active)),
+        )
+        .add_systems(
+            Update,
+            show_joystick.run_if(resource_changed::<ActiveControls>),
+        );
+}
+
+/// The radius of the joystick's base, in pixels. Dragging the knob this far
+/// gives full thrust.
+const JOYSTICK_RADIUS: f32 = 80.0;
+
+const KNOB_RADIUS: f32 = 30.0;
+
+/// Space between the joystick and the bottom left corner of the window.
+const JOYSTICK_MARGIN: f32 = 40.0;
+
+/// How far from the middle, as a fraction of the radius, a drag can start and
+/// still grab the joystick. A little past the edge is forgiving on small screens.
+const GRAB_REACH: f32 = 1.5;
+
+/// How far the knob has to move, as a fraction of the radius, before it thrusts.
+const JOYSTICK_DEAD_ZONE: f32 = 0.15;
+
+/// The base of the joystick.
+#[derive(Component, Debug, Default)]
+struct VirtualJoystick {
+    grab: Option<JoystickGrab>,
+}
+
+/// What is holding the joystick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JoystickGrab {
+    Mouse,
+    Touch(u64),
+}
+
+#[derive(Component, Debug)]
+struct JoystickKnob;
+
+fn joystick_active(active: Res<ActiveControls>) -> bool {
+    active.0 == ControlScheme::Joystick
+}
+
+fn joystick_visibility(active: &ActiveControls) -> Visibility {
+    if active.0 == ControlScheme::Joystick {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn spawn_joystick(mut commands: Commands, active: Res<ActiveControls>) {
+    commands
+        .spawn((
+            Name::new("Virtual Joystick"),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Px(JOYSTICK_MARGIN),
+                    bottom: Px(JOYSTICK_MARGIN),
+                    width: Px(JOYSTICK_RADIUS * 2.0),
+                    height: Px(JOYSTICK_RADIUS * 2.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND.with_alpha(0.3)),
+                border_radius: BorderRadius::MAX,
+                visibility: joystick_visibility(&active),
+                ..default()
+            },
+            VirtualJoystick::default(),
+            StateScoped(Screen::Playing),
+        ))
+        .with_children(|children| {
+            children.spawn((
+                Name::new("Joystick Knob"),
+                NodeBundle {
+                    style: knob_style(Vec2::ZERO),
+                    background_color: BackgroundColor(BUTTON_PRESSED_BACKGROUND.with_alpha(0.8)),
+                    border_radius: BorderRadius::MAX,
+                    ..default()
+                },
+                JoystickKnob,
+            ));
+        });
+}
+
+/// Places the knob `offset` pixels from the middle of the base, down being positive.
+fn knob_style(offset: Vec2) -> Style {
+    Style {
+        position_type: PositionType::Absolute,
+        left: Px(JOYSTICK_RADIUS - KNOB_RADIUS + offset.x),
+        top: Px(JOYSTICK_RADIUS - KNOB_RADIUS + offset.y),
+        width: Px(KNOB_RADIUS * 2.0),
+        height: Px(KNOB_RADIUS * 2.0),
+        ..default()
+    }
+}
+
+fn show_joystick(
+    active: Res<ActiveControls>,
+    mut joysticks: Query<&mut Visibility, With<VirtualJoystick>>,
+) {
+    for mut visibility in joysticks.iter_mut() {
+        *visibility = joystick_visibility(&active);
+    }
+}
+
+/// Where whatever is holding the joystick is, if it is still holding on.
+fn grab_position(
+    grab: JoystickGrab,
+    window: &Window,
+    touches: &Touches,
+    mouse: &ButtonInput<MouseButton>,
+) -> Option<Vec2> {
+    match grab {
+        JoystickGrab::Touch(id) => touches.get_pressed(id).map(Touch::position),
+        JoystickGrab::Mouse if mouse.pressed(MouseButton::Left) => window.cursor_position(),
+        JoystickGrab::Mouse => None,
+    }
+}
+
+fn joystick_thrust(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    touches: Res<Touches>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut joysticks: Query<(&mut VirtualJoystick, &Children)>,
+    mut knobs: Query<&mut Style, With<JoystickKnob>>,
+    mut thrust: ResMut<ThrustInput>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    // in window coordinates, which like the UI have y pointing down
+    let centre = Vec2::new(
+        JOYSTICK_MARGIN + JOYSTICK_RADIUS,
+        window.height() - JOYSTICK_MARGIN - JOYSTICK_RADIUS,
+    );
+    let within_reach = |position: Vec2| position.distance(centre) <= JOYSTICK_RADIUS * GRAB_REACH;
+
+    for (mut joystick, children) in joysticks.iter_mut() {
+        let held = joystick
+            .grab
+            .filter(|grab| grab_position(*grab, window, &touches, &mouse).is_some());
+        let grab = held.or_else(|| {
+            touches
+                .iter_just_pressed()
+                .find(|touch| within_reach(touch.position()))
+                .map(|touch| JoystickGrab::Touch(touch.id()))
+                .or_else(|| {
+                    let clicked = mouse.just_pressed(MouseButton::Left)
+                        && window.cursor_position().is_some_and(within_reach);
+                    clicked.then_some(JoystickGrab::Mouse)
+                })
+        });
+        if joystick.grab != grab {
+            joystick.grab = grab;
+        }
+
+        let offset = grab
+            .and_then(|grab| grab_position(grab, window, &touches, &mouse))
+            .map_or(Vec2::ZERO, |position| {
+                (position - centre).clamp_length_max(JOYSTICK_RADIUS)
+            });
+        let mut knobs = knobs.iter_many_mut(children.iter());
+        while let Some(mut style) = knobs.fetch_next() {
+            let moved = knob_style(offset);
+            if style.left != moved.left || style.top != moved.top {
+                *style = moved;
+            }
+        }
+
+        let stick = Vec2::new(offset.x, -offset.y) / JOYSTICK_RADIUS;
+        thrust.push(scale_past_dead_zone(stick, JOYSTICK_DEAD_ZONE));
+    }
+}
@@ -0,0 +1,459 @@
+//! Player input, read through actions rather than physical keys.
+//!
+//! Gameplay and menus ask whether an [`Action`] is pressed instead of checking
+//! keys themselves. [`InputBindings`] decides which keys and gamepad buttons
+//! trigger each action, so players can rebind their controls and the rest of
+//! the game never needs to know. The state of every action is kept in a
+//! [`ButtonInput<Action>`], which works just like Bevy's own
+//! [`ButtonInput<KeyCode>`].
+//!
+//! Every connected gamepad drives the same actions, so pads can be plugged in
+//! and out at any time. Thrust is also read as an analog [`ThrustInput`], so the
+//! left stick can fly the ship at part thrust.
+//!
+//! Without a keyboard, the ship can be steered with the [`pointer`] or a
+//! [`joystick`] on screen instead. Which of these is in use is decided by the
+//! [`ActiveControls`].
+
+pub mod joystick;
+pub mod pointer;
+
+use bevy::{
+    input::{gamepad::GamepadConnectionEvent, InputSystem},
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::options::{ControlScheme, GameOptions},
+    persistence::Persistence,
+    prelude::*,
+    theme::{gamepad_button_name, gamepad_button_to_string, key_name, key_to_string},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InputBindings>()
+        .init_resource::<ButtonInput<Action>>()
+        .init_resource::<ThrustInput>()
+        .init_resource::<Rebinding>()
+        .init_resource::<SeenInput>()
+        .init_resource::<ActiveControls>()
+        .add_systems(
+            PreUpdate,
+            (
+                capture_rebinding,
+                (track_seen_input, resolve_controls).chain(),
+                update_actions,
+            )
+                .chain()
+                .in_set(ActionSystem)
+                .after(InputSystem),
+        )
+        .add_systems(
+            Update,
+            save_bindings.run_if(resource_changed::<InputBindings>),
+        )
+        .add_plugins((pointer::plugin, joystick::plugin));
+}
+
+/// The key the bindings are saved under.
+const STORAGE_KEY: &str = "bindings";
+
+/// How far the stick has to move before it starts to thrust. Thrust is scaled
+/// from nothing at the dead zone up to full at the edge of the stick.
+const STICK_DEAD_ZONE: f32 = 0.2;
+
+/// How far the stick has to be pushed along an axis to press that thrust
+/// action, for anything that treats thrust as a button.
+const STICK_PRESS_THRESHOLD: f32 = 0.5;
+
+/// Updates the [`ButtonInput<Action>`] and [`ThrustInput`] from the raw input
+/// each frame.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActionSystem;
+
+/// Something the player can do, whatever it is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Action {
+    ThrustUp,
+    ThrustDown,
+    ThrustLeft,
+    ThrustRight,
+    Pause,
+    /// Mutes and unmutes all audio, on any screen
+    Mute,
+    Confirm,
+    Back,
+    MenuUp,
+    MenuDown,
+    /// Moves to the next menu button even where up and down do something else
+    MenuNext,
+}
+
+impl Action {
+    /// Every action, in the order they are listed on the controls screen.
+    pub const ALL: [Action; 11] = [
+        Action::ThrustUp,
+        Action::ThrustDown,
+        Action::ThrustLeft,
+        Action::ThrustRight,
+        Action::Pause,
+        Action::Mute,
+        Action::Confirm,
+        Action::Back,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuNext,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ThrustUp => "Thrust Up",
+            Action::ThrustDown => "Thrust Down",
+            Action::ThrustLeft => "Thrust Left",
+            Action::ThrustRight => "Thrust Right",
+            Action::Pause => "Pause",
+            Action::Mute => "Mute",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::MenuUp => "Menu Up",
+            Action::MenuDown => "Menu Down",
+            Action::MenuNext => "Next Button",
+        }
+    }
+
+    /// The gamepad buttons bound to the action out of the box.
+    fn default_buttons(self) -> Vec<GamepadButtonType> {
+        match self {
+            Action::ThrustUp => vec![GamepadButtonType::DPadUp],
+            Action::ThrustDown => vec![GamepadButtonType::DPadDown],
+            Action::ThrustLeft => vec![GamepadButtonType::DPadLeft],
+            Action::ThrustRight => vec![GamepadButtonType::DPadRight],
+            Action::Pause => vec![GamepadButtonType::Start],
+            Action::Mute => vec![],
+            Action::Confirm => vec![GamepadButtonType::South],
+            Action::Back => vec![GamepadButtonType::East],
+            Action::MenuUp => vec![GamepadButtonType::DPadUp],
+            Action::MenuDown => vec![GamepadButtonType::DPadDown],
+            Action::MenuNext => vec![GamepadButtonType::RightTrigger],
+        }
+    }
+
+    /// The keys bound to the action out of the box.
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::ThrustUp => vec![KeyCode::KeyW, KeyCode::ArrowUp],
+            Action::ThrustDown => vec![KeyCode::KeyS, KeyCode::ArrowDown],
+            Action::ThrustLeft => vec![KeyCode::KeyA, KeyCode::ArrowLeft],
+            Action::ThrustRight => vec![KeyCode::KeyD, KeyCode::ArrowRight],
+            Action::Pause => vec![KeyCode::Escape],
+            Action::Mute => vec![KeyCode::F8],
+            Action::Confirm => vec![KeyCode::Enter],
+            Action::Back => vec![KeyCode::Escape],
+            Action::MenuUp => vec![KeyCode::ArrowUp],
+            Action::MenuDown => vec![KeyCode::ArrowDown],
+            Action::MenuNext => vec![KeyCode::Tab],
+        }
+    }
+}
+
+/// Which keys and gamepad buttons trigger each action. An action can have
+/// several of each, and the same key can trigger more than one action.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    keys: HashMap<Action, Vec<KeyCode>>,
+    buttons: HashMap<Action, Vec<GamepadButtonType>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        InputBindings {
+            keys: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_keys()))
+                .collect(),
+            buttons: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_buttons()))
+                .collect(),
+        }
+    }
+}
+
+impl FromWorld for InputBindings {
+    fn from_world(world: &mut World) -> Self {
+        let mut bindings = InputBindings::default();
+        // anything missing from the saved bindings, like an action added since
+        // they were saved, keeps its default keys
+        if let Some(saved) = world
+            .resource::<Persistence>()
+            .load_json::<InputBindings>(STORAGE_KEY)
+        {
+            bindings.keys.extend(saved.keys);
+            bindings.buttons.extend(saved.buttons);
+        }
+        bindings
+    }
+}
+
+impl InputBindings {
+    /// The keys bound to `action`.
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The gamepad buttons bound to `action`.
+    pub fn buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.buttons.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replace every key bound to `action` with `key`.
+    pub fn bind_key(&mut self, action: Action, key: KeyCode) {
+        self.keys.insert(action, vec![key]);
+    }
+
+    /// Replace every gamepad button bound to `action` with `button`.
+    pub fn bind_button(&mut self, action: Action, button: GamepadButtonType) {
+        self.buttons.insert(action, vec![button]);
+    }
+
+    /// The keys and buttons bound to `action`, written out for the player.
+    pub fn label(&self, action: Action) -> String {
+        let keys = self.keys(action).iter().map(|key| key_to_string(*key));
+        let buttons = self
+            .buttons(action)
+            .iter()
+            .map(|button| gamepad_button_to_string(*button));
+        let names: Vec<_> = keys.chain(buttons).collect();
+        if names.is_empty() {
+            return "Unbound".to_string();
+        }
+        names.join(" / ")
+    }
+
+    /// A short label for `action`, using only its first key.
+    pub fn short_label(&self, action: Action) -> &'static str {
+        self.keys(action)
+            .first()
+            .map_or("-", |key| key_to_string(*key))
+    }
+}
+
+/// The combined thrust from every input, up to 1 in length. Keys give full
+/// thrust, while the stick gives part thrust when it is only pushed part way.
+#[derive(Resource, Debug, Default, Clone, Copy, Deref)]
+pub struct ThrustInput(pub Vec2);
+
+impl ThrustInput {
+    /// Take `thrust` instead if it pushes harder than the thrust so far.
+    pub fn push(&mut self, thrust: Vec2) {
+        if thrust.length_squared() > self.0.length_squared() {
+            self.0 = thrust;
+        }
+    }
+}
+
+/// Which kinds of input the player has used this session.
+#[derive(Resource, Debug, Default)]
+pub struct SeenInput {
+    /// A key or gamepad button has been pressed
+    pub keyboard: bool,
+    /// The screen has been touched
+    pub touch: bool,
+}
+
+/// The control scheme being used right now. Unlike the one in the
+/// [`GameOptions`], this is never [`ControlScheme::Auto`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Deref)]
+pub struct ActiveControls(pub ControlScheme);
+
+impl Default for ActiveControls {
+    fn default() -> Self {
+        ActiveControls(ControlScheme::Pointer)
+    }
+}
+
+/// The action waiting for the next key or button press to become its new binding.
+#[derive(Resource, Debug, Default)]
+pub struct Rebinding(pub Option<Action>);
+
+/// Run condition that is true on the frame `action` is pressed, like Bevy's
+/// [`input_just_pressed`](bevy::input::common_conditions::input_just_pressed).
+pub fn action_just_pressed(action: Action) -> impl FnMut(Res<ButtonInput<Action>>) -> bool + Clone {
+    move |actions: Res<ButtonInput<Action>>| actions.just_pressed(action)
+}
+
+/// Thrust direction from whichever thrust actions are `held`. Not normalised.
+fn thrust_direction(held: impl Fn(Action) -> bool) -> Vec2 {
+    let mut direction = Vec2::ZERO;
+    if held(Action::ThrustUp) {
+        direction.y += 1.0;
+    }
+    if held(Action::ThrustDown) {
+        direction.y -= 1.0;
+    }
+    if held(Action::ThrustLeft) {
+        direction.x -= 1.0;
+    }
+    if held(Action::ThrustRight) {
+        direction.x += 1.0;
+    }
+    direction
+}
+
+/// The left stick of `gamepad`, with the dead zone taken out.
+fn stick_thrust(gamepad: Gamepad, axes: &Axis<GamepadAxis>) -> Vec2 {
+    let axis = |axis_type: GamepadAxisType| {
+        axes.get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or_default()
+    };
+    let stick = Vec2::new(
+        axis(GamepadAxisType::LeftStickX),
+        axis(GamepadAxisType::LeftStickY),
+    );
+    scale_past_dead_zone(stick, STICK_DEAD_ZONE)
+}
+
+/// Rescale a stick position so it goes from nothing at `dead_zone` up to
+/// full length at the edge. Anything inside the dead zone is no thrust at all.
+fn scale_past_dead_zone(stick: Vec2, dead_zone: f32) -> Vec2 {
+    let stick = stick.clamp_length_max(1.0);
+    let length = stick.length();
+    if length <= dead_zone {
+        return Vec2::ZERO;
+    }
+    stick * ((length - dead_zone) / (1.0 - dead_zone) / length)
+}
+
+/// While an action is being rebound, the next key or gamepad button pressed
+/// becomes its binding, Escape included. Clicking or touching the screen
+/// cancels. The press is swallowed so it doesn't also press a button. Keys and
+/// buttons without a name to show are ignored, so a binding never reads as
+/// "???".
+fn capture_rebinding(
+    mut rebinding: ResMut<Rebinding>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut gamepad_input: ResMut<ButtonInput<GamepadButton>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut bindings: ResMut<InputBindings>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    // a click that lands on another action's button starts rebinding that instead
+    if mouse_input.get_just_pressed().len() > 0 || touches.any_just_pressed() {
+        rebinding.0 = None;
+        return;
+    }
+    if let Some(key) = keyboard_input.get_just_pressed().next().copied() {
+        keyboard_input.reset(key);
+        if key_name(key).is_some() {
+            rebinding.0 = None;
+            bindings.bind_key(action, key);
+        }
+    } else if let Some(button) = gamepad_input.get_just_pressed().next().copied() {
+        gamepad_input.reset(button);
+        if gamepad_button_name(button.button_type).is_some() {
+            rebinding.0 = None;
+            bindings.bind_button(action, button.button_type);
+        }
+    }
+}
+
+fn track_seen_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
+    touches: Res<Touches>,
+    mut seen: ResMut<SeenInput>,
+) {
+    if !seen.keyboard
+        && (keyboard_input.get_just_pressed().len() > 0
+            || gamepad_input.get_just_pressed().len() > 0)
+    {
+        seen.keyboard = true;
+    }
+    if !seen.touch && touches.any_just_pressed() {
+        seen.touch = true;
+    }
+}
+
+/// Pick the scheme for [`ControlScheme::Auto`]: keys once they have been used,
+/// otherwise the joystick on touch screens or the pointer everywhere else.
+fn resolve_controls(
+    options: Res<GameOptions>,
+    seen: Res<SeenInput>,
+    mut active: ResMut<ActiveControls>,
+) {
+    let scheme = match options.control_scheme {
+        ControlScheme::Auto if seen.keyboard => ControlScheme::Keyboard,
+        ControlScheme::Auto if seen.touch => ControlScheme::Joystick,
+        ControlScheme::Auto => ControlScheme::Pointer,
+        scheme => scheme,
+    };
+    active.set_if_neq(ActiveControls(scheme));
+}
+
+fn update_actions(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<InputBindings>,
+    mut actions: ResMut<ButtonInput<Action>>,
+    mut thrust: ResMut<ThrustInput>,
+) {
+    // the strongest stick wins, so a second pad lying idle doesn't get in the way
+    let stick = gamepads
+        .iter()
+        .map(|gamepad| stick_thrust(gamepad, &gamepad_axes))
+        .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .unwrap_or_default();
+    let stick_action = |action: Action| match action {
+        Action::ThrustUp | Action::MenuUp => stick.y > STICK_PRESS_THRESHOLD,
+        Action::ThrustDown | Action::MenuDown => stick.y < -STICK_PRESS_THRESHOLD,
+        Action::ThrustLeft => stick.x < -STICK_PRESS_THRESHOLD,
+        Action::ThrustRight => stick.x > STICK_PRESS_THRESHOLD,
+        _ => false,
+    };
+
+    let held = |action: Action| {
+        keyboard_input.any_pressed(bindings.keys(action).iter().copied())
+            || gamepads.iter().any(|gamepad| {
+                gamepad_input.any_pressed(
+                    bindings
+                        .buttons(action)
+                        .iter()
+                        .map(|button_type| GamepadButton::new(gamepad, *button_type)),
+                )
+            })
+    };
+
+    actions.clear();
+    for action in Action::ALL {
+        if held(action) || stick_action(action) {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+
+    // held keys and buttons always give full thrust, the stick only takes over
+    // when it is pushed further
+    thrust.0 = thrust_direction(held).clamp_length_max(1.0);
+    thrust.push(stick);
+}
+
+/// Run condition that is true on the frame a gamepad is unplugged.
+pub fn gamepad_disconnected(mut events: EventReader<GamepadConnectionEvent>) -> bool {
+    events.read().any(GamepadConnectionEvent::disconnected)
+}
+
+fn save_bindings(bindings: Res<InputBindings>, persistence: Res<Persistence>) {
+    // the bindings were only just loaded, there is nothing new to save
+    if bindings.is_added() {
+        return;
+    }
+    persistence.save_json(STORAGE_KEY, &*bindings);
+}
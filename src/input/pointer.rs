@@ -0,0 +1,71 @@
+//! Steering by pointing: the ship thrusts towards the mouse cursor, or towards
+//! wherever the screen is being touched.
+
+use bevy::window::PrimaryWindow;
+
+use super::{update_actions, ActionSystem, ActiveControls, ThrustInput};
+use crate::{
+    game::{options::ControlScheme, player::Player},
+    prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PreUpdate,
+        pointer_thrust
+            .in_set(ActionSystem)
+            .after(update_actions)
+            .run_if(in_state(InGameState::Playing).and_then(pointer_active)),
+    );
+}
+
+/// The ship doesn't thrust while the pointer is this close to it, in pixels,
+/// so it can sit still under the cursor.
+const POINTER_DEAD_ZONE: f32 = 40.0;
+
+/// How far past the dead zone the pointer has to be for full thrust. Closer
+/// than this, thrust is scaled down so the ship can be nudged.
+const POINTER_FULL_THRUST_RANGE: f32 = 150.0;
+
+fn pointer_active(active: Res<ActiveControls>) -> bool {
+    active.0 == ControlScheme::Pointer
+}
+
+/// Where the player is pointing, in window coordinates. A touch wins over the
+/// mouse cursor.
+pub fn pointer_position(window: &Window, touches: &Touches) -> Option<Vec2> {
+    touches
+        .iter()
+        .next()
+        .map(|touch| touch.position())
+        .or_else(|| window.cursor_position())
+}
+
+fn pointer_thrust(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    touches: Res<Touches>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    player: Query<&Transform, With<Player>>,
+    mut thrust: ResMut<ThrustInput>,
+) {
+    let (Ok(window), Ok((camera, camera_transform)), Ok(player_transform)) = (
+        windows.get_single(),
+        cameras.get_single(),
+        player.get_single(),
+    ) else {
+        return;
+    };
+    let Some(target) = pointer_position(window, &touches)
+        .and_then(|position| camera.viewport_to_world_2d(camera_transform, position))
+    else {
+        return;
+    };
+
+    let offset = target - player_transform.translation.truncate();
+    let distance = offset.length();
+    if distance <= POINTER_DEAD_ZONE {
+        return;
+    }
+    let strength = ((distance - POINTER_DEAD_ZONE) / POINTER_FULL_THRUST_RANGE).min(1.0);
+    thrust.push(offset / distance * strength);
+}
@@ -3,6 +3,8 @@ mod audio;
 #[cfg(feature = "dev")]
 mod dev_tools;
 mod game;
+mod input;
+mod persistence;
 mod screens;
 mod theme;
 
@@ -15,9 +17,10 @@ use bevy_trauma_shake::TraumaPlugin;
 
 mod prelude {
     pub use crate::{
-        assets::{BgmHandles, SfxHandles},
+        assets::{ArchetypeHandles, BgmHandles, SfxHandles},
         audio::bgm::BgmCommands as _,
         game::{events::*, resources::*, state::InGameState},
+        input::Action,
         screens::Screen,
         theme::prelude::*,
     };
@@ -27,9 +30,9 @@ mod prelude {
     pub use bevy_trauma_shake::prelude::*;
     pub use rand::{distributions::Uniform, Rng as _};
 
-    /// High-level groupings of systems for the app in the `Update` schedule.
+    /// High-level groupings of systems for the app in the `Update` and `FixedUpdate` schedules.
     /// NOTE: When adding a new variant, make sure to order it in the `configure_sets`
-    /// call below.
+    /// calls below.
     #[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
     pub enum AppSet {
         /// Tick timers.
@@ -42,8 +45,21 @@ mod prelude {
         UpdateScore,
     }
     /// Convert velocity in x and y to a single scaled magnitude value.
+    /// Velocities are in pixels per second, the scale was tuned against
+    /// pixels per tick at [`MOMENTUM_TICK_HZ`](crate::game::movement::MOMENTUM_TICK_HZ).
     pub fn get_magnitude(velocity: &Velocity) -> f32 {
         (velocity.linvel[0].abs() + velocity.linvel[1].abs()) * 0.08
+            / crate::game::movement::MOMENTUM_TICK_HZ
+    }
+
+    /// Step to the next value in `steps` after `current`, wrapping back to the first.
+    /// Used by menu buttons that cycle through a setting.
+    pub fn next_step(steps: &[f32], current: f32) -> f32 {
+        steps
+            .iter()
+            .copied()
+            .find(|step| *step > current + 0.01)
+            .unwrap_or(steps[0])
     }
 }
 
@@ -62,6 +78,18 @@ impl Plugin for AppPlugin {
             )
                 .chain(),
         );
+        // The simulation runs at a fixed rate, ahead of each physics step.
+        app.configure_sets(
+            FixedUpdate,
+            (
+                AppSet::TickTimers,
+                AppSet::RecordInput,
+                AppSet::Update,
+                AppSet::UpdateScore,
+            )
+                .chain()
+                .before(PhysicsSet::SyncBackend),
+        );
 
         // Spawn the main camera.
         app.add_systems(Startup, spawn_camera);
@@ -91,7 +119,7 @@ impl Plugin for AppPlugin {
                 })
                 .set(AudioPlugin {
                     global_volume: GlobalVolume {
-                        volume: Volume::new(0.3),
+                        volume: Volume::new(audio::GLOBAL_VOLUME),
                     },
                     ..default()
                 }),
@@ -99,11 +127,14 @@ impl Plugin for AppPlugin {
 
         // Add other plugins.
         app.add_plugins((
+            persistence::plugin,
+            input::plugin,
             game::plugin,
             screens::plugin,
             theme::plugin,
-            assets::plugin,
+            // audio registers the music manifest asset that assets loads
             audio::plugin,
+            assets::plugin,
             TraumaPlugin,
         ));
 
@@ -119,8 +150,8 @@ fn spawn_camera(mut commands: Commands) {
         Camera2dBundle::default(),
         Shake::default(),
         ShakeSettings {
-            decay_per_second: 5.0, // up from 0.8
-            amplitude: 8.0,        // down from 100.0
+            decay_per_second: 5.0,                     // up from 0.8
+            amplitude: game::options::SHAKE_AMPLITUDE, // down from 100.0
             ..default()
         },
         // Render all UI to this camera.
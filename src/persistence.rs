@@ -0,0 +1,194 @@
+//! Saving small amounts of data between sessions, like high scores.
+//!
+//! Data is stored as JSON strings under a key through the [`Storage`] trait.
+//! Native builds write one file per key into the user's data directory, while
+//! web builds use the browser's `localStorage`.
+
+use crate::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Persistence>();
+}
+
+/// Somewhere to keep strings between sessions.
+pub trait Storage: Send + Sync + 'static {
+    /// Read the value saved under `key`, if there is one.
+    fn load(&self, key: &str) -> Result<Option<String>, StorageError>;
+    /// Save `value` under `key`, replacing anything already there.
+    fn save(&self, key: &str, value: &str) -> Result<(), StorageError>;
+}
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("could not access storage: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("storage is not available: {0}")]
+    Unavailable(String),
+    #[error("could not read or write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The storage backend for this platform.
+#[derive(Resource, Deref)]
+pub struct Persistence(Box<dyn Storage>);
+
+impl Default for Persistence {
+    fn default() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        let storage = native::FileStorage::new(native::data_dir());
+        #[cfg(target_family = "wasm")]
+        let storage = web::LocalStorage;
+        Persistence(Box::new(storage))
+    }
+}
+
+impl Persistence {
+    /// Load and decode the JSON saved under `key`. Missing or unreadable data
+    /// gives `None`, with a warning if it was there but couldn't be read.
+    pub fn load_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let result = self.load(key).and_then(|value| {
+            value
+                .map(|value| serde_json::from_str(&value))
+                .transpose()
+                .map_err(StorageError::from)
+        });
+        result.unwrap_or_else(|error| {
+            warn!("Could not load saved {key}: {error}");
+            None
+        })
+    }
+
+    /// Encode `value` as JSON and save it under `key`, warning if that fails.
+    pub fn save_json<T: Serialize>(&self, key: &str, value: &T) {
+        let result = serde_json::to_string_pretty(value)
+            .map_err(StorageError::from)
+            .and_then(|json| self.save(key, &json));
+        if let Err(error) = result {
+            warn!("Could not save {key}: {error}");
+        }
+    }
+}
+
+/// Seconds since the unix epoch, for timestamping saved data.
+pub fn now_unix_seconds() -> u64 {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs())
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+}
+
+/// Format a unix timestamp as a `YYYY-MM-DD` date, in UTC.
+pub fn format_date(unix_seconds: u64) -> String {
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (unix_seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(not(target_family = "wasm"))]
+mod native {
+    use super::{Storage, StorageError};
+    use std::{fs, io::ErrorKind, path::PathBuf};
+
+    /// Where saved data goes. Set `CHASE_ME_DATA_DIR` to override it.
+    pub fn data_dir() -> PathBuf {
+        let env_dir = |name: &str| std::env::var_os(name).map(PathBuf::from);
+        if let Some(dir) = env_dir("CHASE_ME_DATA_DIR") {
+            return dir;
+        }
+        let base = env_dir("APPDATA")
+            .or_else(|| env_dir("XDG_DATA_HOME"))
+            .or_else(|| env_dir("HOME").map(|home| home.join(".local").join("share")))
+            .unwrap_or_default();
+        base.join("chase-me")
+    }
+
+    /// Saves each key as a JSON file in a directory.
+    pub struct FileStorage {
+        dir: PathBuf,
+    }
+
+    impl FileStorage {
+        pub fn new(dir: PathBuf) -> Self {
+            Self { dir }
+        }
+
+        fn path(&self, key: &str) -> PathBuf {
+            self.dir.join(format!("{key}.json"))
+        }
+    }
+
+    impl Storage for FileStorage {
+        fn load(&self, key: &str) -> Result<Option<String>, StorageError> {
+            match fs::read_to_string(self.path(key)) {
+                Ok(value) => Ok(Some(value)),
+                Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+                Err(error) => Err(error.into()),
+            }
+        }
+
+        fn save(&self, key: &str, value: &str) -> Result<(), StorageError> {
+            fs::create_dir_all(&self.dir)?;
+            // write to a temporary file first so a crash can't leave half a file behind
+            let temporary = self.dir.join(format!("{key}.json.tmp"));
+            fs::write(&temporary, value)?;
+            fs::rename(temporary, self.path(key))?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod web {
+    use super::{Storage, StorageError};
+
+    /// Keys are prefixed so they don't clash with anything else on the same site.
+    const KEY_PREFIX: &str = "chase-me.";
+
+    /// Saves each key in the browser's `localStorage`.
+    pub struct LocalStorage;
+
+    impl LocalStorage {
+        fn storage() -> Result<web_sys::Storage, StorageError> {
+            web_sys::window()
+                .ok_or_else(|| StorageError::Unavailable("no window".to_string()))?
+                .local_storage()
+                .map_err(|error| StorageError::Unavailable(format!("{error:?}")))?
+                .ok_or_else(|| StorageError::Unavailable("localStorage is disabled".to_string()))
+        }
+    }
+
+    impl Storage for LocalStorage {
+        fn load(&self, key: &str) -> Result<Option<String>, StorageError> {
+            Self::storage()?
+                .get_item(&format!("{KEY_PREFIX}{key}"))
+                .map_err(|error| StorageError::Unavailable(format!("{error:?}")))
+        }
+
+        fn save(&self, key: &str, value: &str) -> Result<(), StorageError> {
+            Self::storage()?
+                .set_item(&format!("{KEY_PREFIX}{key}"), value)
+                .map_err(|error| StorageError::Unavailable(format!("{error:?}")))
+        }
+    }
+}
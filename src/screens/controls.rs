@@ -0,0 +1,141 @@
+//! A screen for rebinding controls, reached from the options screen.
+
+use crate::{
+    input::{InputBindings, Rebinding},
+    prelude::*,
+};
+
+const STATE: Screen = Screen::Controls;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(STATE), show_controls_screen)
+        .add_systems(OnExit(STATE), cancel_rebinding)
+        .add_systems(
+            Update,
+            update_binding_values.run_if(in_state(STATE).and_then(
+                resource_changed::<InputBindings>.or_else(resource_changed::<Rebinding>),
+            )),
+        );
+}
+
+/// Label showing the keys bound to an action.
+#[derive(Component, Debug)]
+struct BindingValue(Action);
+
+/// The keys to pick an action to rebind with.
+const ACTION_KEYS: [KeyCode; 11] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+    KeyCode::Minus,
+];
+
+fn show_controls_screen(mut commands: Commands, bindings: Res<InputBindings>) {
+    let enter_options = commands.register_one_shot_system(enter_options);
+    let reset_bindings = commands.register_one_shot_system(reset_bindings);
+    let rebind_systems = Action::ALL.map(|action| {
+        let system = commands.register_one_shot_system(move |mut rebinding: ResMut<Rebinding>| {
+            rebinding.0 = Some(action);
+        });
+        (action, system)
+    });
+
+    commands
+        .ui_root()
+        .insert(StateScoped(STATE))
+        .with_children(|children| {
+            children.header("Controls");
+            children.label("Choose an action, then press its new key or button. Click to cancel.");
+            children
+                .spawn((
+                    Name::new("Binding Grid"),
+                    NodeBundle {
+                        style: Style {
+                            display: Display::Grid,
+                            grid_template_columns: RepeatedGridTrack::auto(2),
+                            column_gap: Val::Px(40.0),
+                            row_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|children| {
+                    for ((action, system), key) in rebind_systems.into_iter().zip(ACTION_KEYS) {
+                        children
+                            .spawn((
+                                Name::new(action.name()),
+                                NodeBundle {
+                                    style: Style {
+                                        flex_direction: FlexDirection::Row,
+                                        align_items: AlignItems::Center,
+                                        column_gap: Val::Px(10.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                            ))
+                            .with_children(|children| {
+                                children.button(action.name(), system, Some(key));
+                                children.label(bindings.label(action)).insert((
+                                    Style {
+                                        width: Val::Px(200.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BindingValue(action),
+                                ));
+                            });
+                    }
+                });
+            children.shortcut_button(
+                "Reset",
+                reset_bindings,
+                KeyCode::KeyR,
+                GamepadButtonType::North,
+            );
+            children.action_button("Back", enter_options, Action::Back);
+        });
+}
+
+fn update_binding_values(
+    bindings: Res<InputBindings>,
+    rebinding: Res<Rebinding>,
+    values: Query<(&BindingValue, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    // the label widget keeps its text on a child entity
+    for (BindingValue(action), children) in values.iter() {
+        let value = if rebinding.0 == Some(*action) {
+            "Press a key or button...".to_string()
+        } else {
+            bindings.label(*action)
+        };
+        let mut iter = texts.iter_many_mut(children.iter());
+        while let Some(mut text) = iter.fetch_next() {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+fn reset_bindings(mut bindings: ResMut<InputBindings>, mut rebinding: ResMut<Rebinding>) {
+    *bindings = InputBindings::default();
+    rebinding.0 = None;
+}
+
+fn cancel_rebinding(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+/// Go back to the options, which still remember the screen they were opened from.
+fn enter_options(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Options);
+}
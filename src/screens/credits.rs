@@ -24,10 +24,10 @@ fn show_credits_screen(mut commands: Commands) {
             children.label("Background music created for non-commercial use with https://suno.com");
             children.label("Sound effects taken from https://pixabay.com");
 
-            children.button("Back", enter_title, Some(KeyCode::Escape));
+            children.action_button("Back", enter_title, Action::Back);
         });
 
-    commands.play_bgm(BgmHandles::PATH_CREDITS);
+    commands.play_bgm(BgmHandles::TRACK_CREDITS);
 }
 
 fn stop_bgm(mut commands: Commands) {
@@ -0,0 +1,106 @@
+//! A screen showing the saved high scores, reached from the title screen.
+
+use super::enter_title;
+use crate::{
+    game::{
+        difficulty::{Difficulty, DifficultyLevel},
+        high_scores::{GameMode, HighScores},
+    },
+    persistence::format_date,
+    prelude::*,
+};
+
+const STATE: Screen = Screen::HighScores;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShownDifficulty>()
+        .add_systems(
+            OnEnter(STATE),
+            (show_chosen_difficulty, show_high_scores_screen).chain(),
+        )
+        .add_systems(
+            Update,
+            update_score_rows.run_if(in_state(STATE).and_then(resource_changed::<ShownDifficulty>)),
+        );
+}
+
+/// Which difficulty's table is on screen.
+#[derive(Resource, Debug, Default)]
+struct ShownDifficulty(DifficultyLevel);
+
+/// Container for the rows of the table, refilled when the difficulty changes.
+#[derive(Component, Debug)]
+struct ScoreRows;
+
+/// Start on the table for the difficulty the player last chose.
+fn show_chosen_difficulty(mut shown: ResMut<ShownDifficulty>, difficulty: Res<Difficulty>) {
+    shown.0 = difficulty.level;
+}
+
+fn show_high_scores_screen(mut commands: Commands) {
+    let enter_title = commands.register_one_shot_system(enter_title);
+    let cycle_difficulty = commands.register_one_shot_system(cycle_shown_difficulty);
+
+    commands
+        .ui_root()
+        .insert(StateScoped(STATE))
+        .with_children(|children| {
+            children.header("High Scores");
+            children.spawn((
+                Name::new("Score Rows"),
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ScoreRows,
+            ));
+
+            children.shortcut_button(
+                "Difficulty",
+                cycle_difficulty,
+                KeyCode::KeyD,
+                GamepadButtonType::West,
+            );
+            children.action_button("Back", enter_title, Action::Back);
+        });
+}
+
+fn cycle_shown_difficulty(mut shown: ResMut<ShownDifficulty>) {
+    shown.0 = shown.0.next();
+}
+
+fn update_score_rows(
+    mut commands: Commands,
+    shown: Res<ShownDifficulty>,
+    mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+    rows: Query<Entity, With<ScoreRows>>,
+) {
+    let entries = high_scores.entries(*mode, shown.0);
+    for rows in rows.iter() {
+        commands
+            .entity(rows)
+            .despawn_descendants()
+            .with_children(|children| {
+                children.label(format!("{} - {}", mode.name(), shown.0.name()));
+                if entries.is_empty() {
+                    children.label("No scores yet");
+                }
+                for (rank, entry) in entries.iter().enumerate() {
+                    children.label(format!(
+                        "{:>2}. {:<3} {:>7} {:>6.1}s {}",
+                        rank + 1,
+                        entry.name,
+                        entry.score,
+                        entry.runtime,
+                        format_date(entry.timestamp)
+                    ));
+                }
+            });
+    }
+}
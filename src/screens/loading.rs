@@ -1,8 +1,11 @@
 //! A loading screen during which game assets are loaded.
 //! This reduces stuttering, especially for audio on WASM.
 
-use crate::prelude::*;
-use bevy::utils::HashMap;
+use crate::{
+    audio::{music::MusicManifest, sfx::SfxManifest},
+    prelude::*,
+};
+use bevy::{asset::RecursiveDependencyLoadState, utils::HashMap};
 
 const STATE: Screen = Screen::Loading;
 
@@ -10,16 +13,30 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(STATE), show_loading_screen);
     app.add_systems(
         Update,
-        continue_to_title.run_if(in_state(STATE).and_then(all_assets_loaded)),
+        (
+            check_sound_manifest,
+            check_music_manifest,
+            continue_to_title,
+        )
+            .chain()
+            .run_if(in_state(STATE).and_then(all_assets_loaded)),
+    );
+    app.add_systems(
+        Update,
+        report_failed_assets.run_if(in_state(STATE).and_then(any_assets_failed)),
     );
 }
 
+/// The text on the loading screen.
+#[derive(Component, Debug)]
+struct LoadingText;
+
 fn show_loading_screen(mut commands: Commands) {
     commands
         .ui_root()
         .insert(StateScoped(STATE))
         .with_children(|children| {
-            children.label("Loading...");
+            children.label("Loading...").insert(LoadingText);
         });
 }
 
@@ -28,9 +45,64 @@ fn all_assets_loaded(
     // image_handles: Res<ImageHandles>,
     sfx_handles: Res<SfxHandles>,
     bgm_handles: Res<BgmHandles>,
+    archetype_handles: Res<ArchetypeHandles>,
 ) -> bool {
     // image_handles.all_loaded(&asset_server) &&
-    sfx_handles.all_loaded(&asset_server) && bgm_handles.all_loaded(&asset_server)
+    sfx_handles.all_loaded(&asset_server)
+        && bgm_handles.all_loaded(&asset_server)
+        && archetype_handles.all_loaded(&asset_server)
+}
+
+fn any_assets_failed(
+    asset_server: Res<AssetServer>,
+    sfx_handles: Res<SfxHandles>,
+    bgm_handles: Res<BgmHandles>,
+    archetype_handles: Res<ArchetypeHandles>,
+) -> bool {
+    sfx_handles.any_failed(&asset_server)
+        || bgm_handles.any_failed(&asset_server)
+        || archetype_handles.any_failed(&asset_server)
+}
+
+/// Loading won't finish if a data file or one of the files it lists is
+/// broken, so say so rather than sitting on the loading screen forever.
+fn report_failed_assets(labels: Query<&Children, With<LoadingText>>, mut texts: Query<&mut Text>) {
+    // the label widget keeps its text on a child entity
+    for children in labels.iter() {
+        let mut iter = texts.iter_many_mut(children.iter());
+        while let Some(mut text) = iter.fetch_next() {
+            text.sections[0].value = "Could not load the game data, see the log for details".into();
+        }
+    }
+}
+
+/// Report everything wrong with the sound effect manifest, including sounds
+/// the game plays that it doesn't list.
+fn check_sound_manifest(sfx_handles: Res<SfxHandles>, manifests: Res<Assets<SfxManifest>>) {
+    let Some(manifest) = sfx_handles.manifest(&manifests) else {
+        return;
+    };
+    for problem in manifest.problems() {
+        error!(
+            "Sound effect manifest {}: {problem}",
+            SfxHandles::PATH_SOUNDS
+        );
+    }
+}
+
+/// Report everything wrong with the music manifest, including tracks and
+/// stingers the game plays that it doesn't list.
+fn check_music_manifest(
+    bgm_handles: Res<BgmHandles>,
+    manifests: Res<Assets<MusicManifest>>,
+    sources: Res<Assets<AudioSource>>,
+) {
+    let Some(manifest) = bgm_handles.manifest(&manifests) else {
+        return;
+    };
+    for problem in manifest.problems(&sources) {
+        error!("Music manifest {}: {problem}", BgmHandles::PATH_MUSIC);
+    }
 }
 
 fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
@@ -41,6 +113,9 @@ fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
 /// loaded.
 trait AllLoaded {
     fn all_loaded(&self, asset_server: &AssetServer) -> bool;
+
+    /// Whether any of the assets, or anything they depend on, failed to load.
+    fn any_failed(&self, asset_server: &AssetServer) -> bool;
 }
 
 impl<T: Asset> AllLoaded for HashMap<String, Handle<T>> {
@@ -48,12 +123,10 @@ impl<T: Asset> AllLoaded for HashMap<String, Handle<T>> {
         self.values()
             .all(|x| asset_server.is_loaded_with_dependencies(x))
     }
-}
 
-impl<T: Asset> AllLoaded for HashMap<String, Vec<Handle<T>>> {
-    fn all_loaded(&self, asset_server: &AssetServer) -> bool {
-        self.values()
-            .flatten()
-            .all(|x| asset_server.is_loaded_with_dependencies(x))
+    fn any_failed(&self, asset_server: &AssetServer) -> bool {
+        self.values().any(|x| {
+            asset_server.recursive_dependency_load_state(x) == RecursiveDependencyLoadState::Failed
+        })
     }
 }
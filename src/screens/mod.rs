@@ -1,7 +1,10 @@
 //! The game's main screen states and transitions between them.
 
+mod controls;
 mod credits;
+mod high_scores;
 mod loading;
+mod options;
 mod pause;
 mod playing;
 mod prep;
@@ -20,6 +23,9 @@ pub(super) fn plugin(app: &mut App) {
         loading::plugin,
         title::plugin,
         credits::plugin,
+        high_scores::plugin,
+        options::plugin,
+        controls::plugin,
         playing::plugin,
         pause::plugin,
         prep::plugin,
@@ -39,6 +45,12 @@ pub enum Screen {
     Title,
     /// The credits screen that appears when the player selects the credits option.
     Credits,
+    /// The high score table that can be reached from the title screen.
+    HighScores,
+    /// The options screen that can be reached from the title and pause screens.
+    Options,
+    /// The controls screen that can be reached from the options screen.
+    Controls,
     /// The prepare screen that appears when the player starts preparing to play the game.
     Preparation,
     /// The playing screen that appears when the player starts playing the game.
@@ -81,6 +93,16 @@ pub fn enter_credits(mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Credits);
 }
 
+/// Transition to the high scores screen.
+pub fn enter_high_scores(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::HighScores);
+}
+
+/// Ask for the options screen, which returns to the current screen when left.
+pub fn request_options(mut options_requested: EventWriter<OptionsRequested>) {
+    options_requested.send(OptionsRequested);
+}
+
 /// Transition to the pause screen.
 pub fn enter_pause(
     mut next_screen: ResMut<NextState<Screen>>,
@@ -0,0 +1,247 @@
+//! An options screen that can be reached from the title and pause screens.
+
+use crate::{
+    game::options::{ControlScheme, GameOptions},
+    input::ActiveControls,
+    prelude::*,
+};
+
+const STATE: Screen = Screen::Options;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<OptionsReturn>()
+        .add_systems(Update, open_options.run_if(on_event::<OptionsRequested>()))
+        .add_systems(OnEnter(STATE), show_options_screen)
+        .add_systems(
+            Update,
+            update_option_values.run_if(in_state(STATE).and_then(
+                resource_changed::<GameOptions>.or_else(resource_changed::<ActiveControls>),
+            )),
+        );
+}
+
+/// The screen to go back to when leaving the options.
+#[derive(Resource, Debug, Default)]
+struct OptionsReturn(Screen);
+
+/// The settings on the options screen, each with a button that cycles it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionKind {
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    ScreenShake,
+    ParticleDensity,
+    Fullscreen,
+    ControlScheme,
+    FlightModel,
+}
+
+impl OptionKind {
+    const ALL: [OptionKind; 8] = [
+        OptionKind::MasterVolume,
+        OptionKind::MusicVolume,
+        OptionKind::EffectsVolume,
+        OptionKind::ScreenShake,
+        OptionKind::ParticleDensity,
+        OptionKind::Fullscreen,
+        OptionKind::ControlScheme,
+        OptionKind::FlightModel,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            OptionKind::MasterVolume => "Master",
+            OptionKind::MusicVolume => "Music",
+            OptionKind::EffectsVolume => "Effects",
+            OptionKind::ScreenShake => "Shake",
+            OptionKind::ParticleDensity => "Particles",
+            OptionKind::Fullscreen => "Display",
+            OptionKind::ControlScheme => "Steering",
+            OptionKind::FlightModel => "Flight",
+        }
+    }
+
+    fn key(self) -> KeyCode {
+        match self {
+            OptionKind::MasterVolume => KeyCode::Digit1,
+            OptionKind::MusicVolume => KeyCode::Digit2,
+            OptionKind::EffectsVolume => KeyCode::Digit3,
+            OptionKind::ScreenShake => KeyCode::Digit4,
+            OptionKind::ParticleDensity => KeyCode::Digit5,
+            OptionKind::Fullscreen => KeyCode::Digit6,
+            OptionKind::ControlScheme => KeyCode::Digit7,
+            OptionKind::FlightModel => KeyCode::Digit8,
+        }
+    }
+
+    /// How the current value is shown next to the button.
+    fn value(self, options: &GameOptions, controls: &ActiveControls) -> String {
+        let percent = |value: f32| {
+            if value <= 0.0 {
+                "Off".to_string()
+            } else {
+                format!("{:.0}%", value * 100.0)
+            }
+        };
+        match self {
+            OptionKind::MasterVolume => percent(options.master_volume),
+            OptionKind::MusicVolume => percent(options.music_volume),
+            OptionKind::EffectsVolume => percent(options.effects_volume),
+            OptionKind::ScreenShake => percent(options.screen_shake),
+            OptionKind::ParticleDensity => percent(options.particle_density),
+            OptionKind::Fullscreen if options.fullscreen => "Fullscreen".to_string(),
+            OptionKind::Fullscreen => "Windowed".to_string(),
+            OptionKind::ControlScheme => options.control_scheme.name().to_string(),
+            // the pointer and joystick always fly directly
+            OptionKind::FlightModel if controls.0 != ControlScheme::Keyboard => {
+                format!("{} (keys only)", options.flight_model.name())
+            }
+            OptionKind::FlightModel => options.flight_model.name().to_string(),
+        }
+    }
+
+    /// The options with this setting moved on to its next value.
+    fn cycle(self, options: &GameOptions) -> GameOptions {
+        const VOLUMES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+        let mut options = options.clone();
+        match self {
+            OptionKind::MasterVolume => {
+                options.master_volume = next_step(&VOLUMES, options.master_volume);
+            }
+            OptionKind::MusicVolume => {
+                options.music_volume = next_step(&VOLUMES, options.music_volume);
+            }
+            OptionKind::EffectsVolume => {
+                options.effects_volume = next_step(&VOLUMES, options.effects_volume);
+            }
+            OptionKind::ScreenShake => {
+                options.screen_shake = next_step(&[0.0, 0.5, 1.0, 1.5, 2.0], options.screen_shake);
+            }
+            OptionKind::ParticleDensity => {
+                options.particle_density =
+                    next_step(&[0.0, 0.25, 0.5, 1.0], options.particle_density);
+            }
+            OptionKind::Fullscreen => options.fullscreen = !options.fullscreen,
+            OptionKind::ControlScheme => options.control_scheme = options.control_scheme.next(),
+            OptionKind::FlightModel => options.flight_model = options.flight_model.next(),
+        }
+        options
+    }
+}
+
+/// Label showing the current value of a setting.
+#[derive(Component, Debug)]
+struct OptionValue(OptionKind);
+
+/// Remember where the options were opened from, then show them.
+fn open_options(
+    mut requests: EventReader<OptionsRequested>,
+    screen: Res<State<Screen>>,
+    mut options_return: ResMut<OptionsReturn>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    requests.clear();
+    if *screen.get() == STATE {
+        return;
+    }
+    options_return.0 = screen.get().clone();
+    next_screen.set(STATE);
+}
+
+fn show_options_screen(
+    mut commands: Commands,
+    options: Res<GameOptions>,
+    controls: Res<ActiveControls>,
+) {
+    let leave_options = commands.register_one_shot_system(leave_options);
+    let enter_controls = commands.register_one_shot_system(enter_controls);
+    let cycle_systems = OptionKind::ALL.map(|kind| {
+        let system = commands.register_one_shot_system(
+            move |options: Res<GameOptions>, mut updates: EventWriter<UpdateOptions>| {
+                updates.send(UpdateOptions(kind.cycle(&options)));
+            },
+        );
+        (kind, system)
+    });
+
+    commands
+        .ui_root()
+        .insert(StateScoped(STATE))
+        .with_children(|children| {
+            children.header("Options");
+            children
+                .spawn((
+                    Name::new("Option Grid"),
+                    NodeBundle {
+                        style: Style {
+                            display: Display::Grid,
+                            grid_template_columns: RepeatedGridTrack::auto(2),
+                            column_gap: Val::Px(40.0),
+                            row_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|children| {
+                    for (kind, system) in cycle_systems {
+                        children
+                            .spawn((
+                                Name::new(kind.name()),
+                                NodeBundle {
+                                    style: Style {
+                                        flex_direction: FlexDirection::Row,
+                                        align_items: AlignItems::Center,
+                                        column_gap: Val::Px(10.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                            ))
+                            .with_children(|children| {
+                                children.button(kind.name(), system, Some(kind.key()));
+                                children.label(kind.value(&options, &controls)).insert((
+                                    Style {
+                                        width: Val::Px(200.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    OptionValue(kind),
+                                ));
+                            });
+                    }
+                });
+            children.shortcut_button(
+                "Controls",
+                enter_controls,
+                KeyCode::Digit9,
+                GamepadButtonType::North,
+            );
+            children.action_button("Back", leave_options, Action::Back);
+        });
+}
+
+fn update_option_values(
+    options: Res<GameOptions>,
+    controls: Res<ActiveControls>,
+    values: Query<(&OptionValue, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    // the label widget keeps its text on a child entity
+    for (OptionValue(kind), children) in values.iter() {
+        let mut iter = texts.iter_many_mut(children.iter());
+        while let Some(mut text) = iter.fetch_next() {
+            text.sections[0].value = kind.value(&options, &controls);
+        }
+    }
+}
+
+fn enter_controls(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Controls);
+}
+
+fn leave_options(options_return: Res<OptionsReturn>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(options_return.0.clone());
+}
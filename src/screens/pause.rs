@@ -1,6 +1,6 @@
 //! The pause screen that appears when the game is paused.
 
-use super::{enter_game, enter_prep, enter_title};
+use super::{enter_game, enter_prep, enter_title, request_options};
 use crate::prelude::*;
 
 const STATE: Screen = Screen::Paused;
@@ -12,14 +12,26 @@ pub(super) fn plugin(app: &mut App) {
 fn show_pause_screen(mut commands: Commands) {
     let enter_playing = commands.register_one_shot_system(enter_game);
     let enter_prep = commands.register_one_shot_system(enter_prep);
+    let request_options = commands.register_one_shot_system(request_options);
     let enter_title = commands.register_one_shot_system(enter_title);
 
     commands
         .ui_root()
         .insert(StateScoped(STATE))
         .with_children(|children| {
-            children.button("Continue", enter_playing, Some(KeyCode::Escape));
-            children.button("Restart", enter_prep, Some(KeyCode::KeyR));
-            children.button("Menu", enter_title, Some(KeyCode::KeyM));
+            children.action_button("Continue", enter_playing, Action::Pause);
+            children.shortcut_button(
+                "Restart",
+                enter_prep,
+                KeyCode::KeyR,
+                GamepadButtonType::West,
+            );
+            children.shortcut_button(
+                "Options",
+                request_options,
+                KeyCode::KeyO,
+                GamepadButtonType::Select,
+            );
+            children.shortcut_button("Menu", enter_title, KeyCode::KeyM, GamepadButtonType::North);
         });
 }
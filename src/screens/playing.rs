@@ -1,9 +1,12 @@
 //! The screen state for the main game loop.
 
 use super::enter_pause;
-use crate::prelude::*;
-use bevy::input::common_conditions::input_just_pressed;
-use ui_palette::LABEL_TEXT;
+use crate::{
+    game::{player::PlayerHealth, waves::WaveDirector},
+    input::{action_just_pressed, gamepad_disconnected},
+    prelude::*,
+};
+use ui_palette::{HEADER_TEXT, LABEL_TEXT};
 
 const STATE: Screen = Screen::Playing;
 
@@ -13,11 +16,15 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         Update,
-        enter_pause.run_if(in_state(STATE).and_then(input_just_pressed(KeyCode::Escape))),
+        // losing the pad mid-game pauses, so the ship isn't left flying blind
+        enter_pause.run_if(
+            in_state(STATE)
+                .and_then(action_just_pressed(Action::Pause).or_else(gamepad_disconnected)),
+        ),
     );
     app.add_systems(
         Update,
-        update_score
+        (update_score, show_wave_banner, hide_wave_banner)
             .in_set(AppSet::UpdateScore)
             .run_if(in_state(InGameState::Playing)),
     );
@@ -35,11 +42,63 @@ fn update_score(
     mut query: Query<&mut Text, With<ScoreIndicator>>,
     score: Res<Score>,
     runtime: Res<GameTime>,
+    waves: Res<WaveDirector>,
+    player: Query<&PlayerHealth>,
 ) {
+    let (hull, lives) = player
+        .get_single()
+        .map_or((0.0, 0), |health| (health.hull.max(0.0), health.lives));
     for mut text in query.iter_mut() {
         // Update the text with the current score
-        text.sections[0].value =
-            format!("Score: {} | Runtime: {:.1}s", score.0 as u32, runtime.time);
+        text.sections[0].value = format!(
+            "Score: {} | Wave: {} | Hull: {:.1} | Lives: {} | Runtime: {:.1}s",
+            score.0 as u32,
+            waves.number(),
+            hull,
+            lives,
+            runtime.time
+        );
+    }
+}
+
+/// Banner announcing the start and end of each wave.
+#[derive(Component, Debug)]
+struct WaveBanner {
+    /// How much longer the banner stays on screen
+    timer: Timer,
+}
+
+const WAVE_BANNER_SECONDS: f32 = 2.0;
+
+fn show_wave_banner(
+    mut query: Query<(&mut Text, &mut Visibility, &mut WaveBanner)>,
+    mut started_events: EventReader<WaveStarted>,
+    mut cleared_events: EventReader<WaveCleared>,
+) {
+    let message = cleared_events
+        .read()
+        .map(|event| format!("Wave {} cleared!", event.number))
+        .chain(
+            started_events
+                .read()
+                .map(|event| format!("Wave {}", event.number)),
+        )
+        .last();
+    let Some(message) = message else {
+        return;
+    };
+    for (mut text, mut visibility, mut banner) in query.iter_mut() {
+        text.sections[0].value.clone_from(&message);
+        *visibility = Visibility::Inherited;
+        banner.timer.reset();
+    }
+}
+
+fn hide_wave_banner(time: Res<Time>, mut query: Query<(&mut Visibility, &mut WaveBanner)>) {
+    for (mut visibility, mut banner) in query.iter_mut() {
+        if banner.timer.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Hidden;
+        }
     }
 }
 
@@ -52,7 +111,7 @@ fn add_score_ui(mut commands: Commands) {
                 Name::new("Score"),
                 NodeBundle {
                     style: Style {
-                        width: Val::Px(500.0),
+                        width: Val::Px(700.0),
                         justify_content: JustifyContent::End,
                         align_items: AlignItems::End,
                         position_type: PositionType::Absolute,
@@ -66,7 +125,7 @@ fn add_score_ui(mut commands: Commands) {
                     Name::new("Score Text"),
                     ScoreIndicator,
                     TextBundle::from_section(
-                        "Score: 0 | Runtime: 0.0s",
+                        "Score: 0 | Wave: 0 | Hull: 0.0 | Lives: 0 | Runtime: 0.0s",
                         TextStyle {
                             font_size: 24.0,
                             color: LABEL_TEXT,
@@ -75,5 +134,23 @@ fn add_score_ui(mut commands: Commands) {
                     ),
                 ));
             });
+
+            children.spawn((
+                Name::new("Wave Banner"),
+                WaveBanner {
+                    timer: Timer::from_seconds(WAVE_BANNER_SECONDS, TimerMode::Once),
+                },
+                TextBundle {
+                    visibility: Visibility::Hidden,
+                    ..TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: HEADER_TEXT,
+                            ..default()
+                        },
+                    )
+                },
+            ));
         });
 }
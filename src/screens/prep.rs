@@ -1,19 +1,49 @@
 //! A credits screen that can be accessed from the title screen.
 
 use super::{enter_game, enter_title};
-use crate::game::level::SpawnLevel;
-use crate::prelude::*;
+use crate::game::{
+    difficulty::{Difficulty, DifficultyLevel},
+    level::SpawnLevel,
+};
+use crate::{
+    game::options::{ControlScheme, FlightModel, GameOptions},
+    input::{ActiveControls, InputBindings},
+    prelude::*,
+    theme::interaction::KeyTrigger,
+};
 
 const STATE: Screen = Screen::Preparation;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(STATE), show_prep_screen)
-        .add_systems(OnExit(STATE), spawn_level);
+        .add_systems(OnExit(STATE), spawn_level)
+        .add_systems(
+            Update,
+            update_difficulty_ui.run_if(in_state(STATE).and_then(resource_changed::<Difficulty>)),
+        );
 }
 
-fn show_prep_screen(mut commands: Commands) {
+/// Label describing the chosen difficulty.
+#[derive(Component, Debug)]
+struct DifficultyDescription;
+
+/// Buttons for tweaking the custom difficulty, only shown when it is chosen.
+#[derive(Component, Debug)]
+struct CustomDifficultyControls;
+
+fn show_prep_screen(
+    mut commands: Commands,
+    difficulty: Res<Difficulty>,
+    bindings: Res<InputBindings>,
+    controls: Res<ActiveControls>,
+    options: Res<GameOptions>,
+) {
     let enter_game = commands.register_one_shot_system(enter_game);
     let enter_title = commands.register_one_shot_system(enter_title);
+    let cycle_difficulty = commands.register_one_shot_system(cycle_difficulty);
+    let cycle_enemy_strength = commands.register_one_shot_system(cycle_enemy_strength);
+    let cycle_spawn_pace = commands.register_one_shot_system(cycle_spawn_pace);
+    let cycle_lives = commands.register_one_shot_system(cycle_lives);
 
     commands
         .ui_root()
@@ -21,25 +51,138 @@ fn show_prep_screen(mut commands: Commands) {
         .with_children(|children| {
             children.header("Ready to play?");
 
-            children.label("Use arrows or WASD to move the player.");
+            children.label(describe_controls(
+                &controls,
+                options.flight_model,
+                &bindings,
+            ));
             children.label("Escape the enemies");
 
-            children.button("Start", enter_game, Some(KeyCode::Space));
-            children.button("Back", enter_title, Some(KeyCode::Escape));
+            children.shortcut_button(
+                "Difficulty",
+                cycle_difficulty,
+                KeyCode::KeyD,
+                GamepadButtonType::West,
+            );
+            children
+                .label(describe_difficulty(&difficulty))
+                .insert(DifficultyDescription);
+            children
+                .spawn((
+                    Name::new("Custom Difficulty"),
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(10.0),
+                            display: custom_controls_display(&difficulty),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    CustomDifficultyControls,
+                ))
+                .with_children(|children| {
+                    children.button("Enemies", cycle_enemy_strength, Some(KeyCode::Digit1));
+                    children.button("Pace", cycle_spawn_pace, Some(KeyCode::Digit2));
+                    children.button("Lives", cycle_lives, Some(KeyCode::Digit3));
+                });
+
+            // space has always started the game, so it still does alongside confirm
+            children
+                .action_button("Start", enter_game, Action::Confirm)
+                .insert(KeyTrigger::new(KeyCode::Space));
+            children.action_button("Back", enter_title, Action::Back);
         });
 }
 
-fn spawn_level(mut commands: Commands) {
-    commands.add(SpawnLevel);
-    commands.play_bgm(
-        get_random_array_element(&[BgmHandles::PATH_GAMEPLAY1, BgmHandles::PATH_GAMEPLAY2])
-            .to_string(),
-    );
+/// How to move the player with the controls in use.
+fn describe_controls(
+    controls: &ActiveControls,
+    flight_model: FlightModel,
+    bindings: &InputBindings,
+) -> String {
+    match controls.0 {
+        ControlScheme::Pointer => "Point where you want the player to go.".to_string(),
+        ControlScheme::Joystick => "Drag the joystick to move the player.".to_string(),
+        ControlScheme::Keyboard if flight_model == FlightModel::RotateAndThrust => format!(
+            "Use {} {} to turn and {} to thrust.",
+            bindings.short_label(Action::ThrustLeft),
+            bindings.short_label(Action::ThrustRight),
+            bindings.short_label(Action::ThrustUp)
+        ),
+        ControlScheme::Auto | ControlScheme::Keyboard => {
+            let thrust_keys = [
+                Action::ThrustUp,
+                Action::ThrustLeft,
+                Action::ThrustDown,
+                Action::ThrustRight,
+            ]
+            .map(|action| bindings.short_label(action));
+            format!("Use {} to move the player.", thrust_keys.join(" "))
+        }
+    }
+}
+
+/// One line summary of what the chosen difficulty means.
+fn describe_difficulty(difficulty: &Difficulty) -> String {
+    let settings = difficulty.settings();
+    format!(
+        "{}: enemies x{:.1}, pace x{:.1}, {} extra lives, score x{:.1}",
+        difficulty.level.name(),
+        settings.enemy_strength,
+        1.0 / settings.spawn_interval,
+        settings.lives,
+        settings.score_multiplier
+    )
+}
+
+fn custom_controls_display(difficulty: &Difficulty) -> Display {
+    if difficulty.level == DifficultyLevel::Custom {
+        Display::Flex
+    } else {
+        Display::None
+    }
+}
+
+fn update_difficulty_ui(
+    difficulty: Res<Difficulty>,
+    descriptions: Query<&Children, With<DifficultyDescription>>,
+    mut texts: Query<&mut Text>,
+    mut controls: Query<&mut Style, With<CustomDifficultyControls>>,
+) {
+    // the label widget keeps its text on a child entity
+    for children in descriptions.iter() {
+        let mut iter = texts.iter_many_mut(children.iter());
+        while let Some(mut text) = iter.fetch_next() {
+            text.sections[0].value = describe_difficulty(&difficulty);
+        }
+    }
+    for mut style in controls.iter_mut() {
+        style.display = custom_controls_display(&difficulty);
+    }
 }
 
-fn get_random_array_element<T>(array: &[T]) -> &T {
-    let mut rng = rand::thread_rng();
-    let range = rand::distributions::Uniform::new(0, array.len());
-    let index = rng.sample(range);
-    &array[index]
+fn cycle_difficulty(mut difficulty: ResMut<Difficulty>) {
+    difficulty.level = difficulty.level.next();
+}
+
+fn cycle_enemy_strength(mut difficulty: ResMut<Difficulty>) {
+    let custom = &mut difficulty.custom;
+    custom.enemy_strength = next_step(&[0.6, 0.8, 1.0, 1.2, 1.5], custom.enemy_strength);
+}
+
+fn cycle_spawn_pace(mut difficulty: ResMut<Difficulty>) {
+    // step through the pace, a faster pace is a shorter interval between spawns
+    let custom = &mut difficulty.custom;
+    let pace = next_step(&[0.5, 0.75, 1.0, 1.5, 2.0], 1.0 / custom.spawn_interval);
+    custom.spawn_interval = 1.0 / pace;
+}
+
+fn cycle_lives(mut difficulty: ResMut<Difficulty>) {
+    difficulty.custom.lives = (difficulty.custom.lives + 1) % 6;
+}
+
+fn spawn_level(mut commands: Commands) {
+    commands.add(SpawnLevel);
+    commands.play_bgm(BgmHandles::TRACK_GAMEPLAY);
 }
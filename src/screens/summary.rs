@@ -1,19 +1,113 @@
 //! A summary screen that shows when the game ends.
+//!
+//! When the score makes the high score table, the player enters their
+//! initials arcade style before the score is saved. Letters can be typed, or
+//! picked with the menu and thrust actions or by clicking the + and - buttons.
 
 use super::{enter_prep, enter_title};
-use crate::prelude::*;
+use crate::{
+    game::{
+        difficulty::Difficulty,
+        high_scores::{GameMode, HighScoreEntry, HighScores},
+    },
+    persistence::now_unix_seconds,
+    prelude::*,
+};
+use bevy::{
+    ecs::system::RunSystemOnce as _,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+};
+use ui_palette::{BUTTON_HOVERED_BACKGROUND, BUTTON_PRESSED_BACKGROUND, NODE_BACKGROUND};
 
 const STATE: Screen = Screen::Summary;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(STATE), show_summary_screen);
-    app.add_systems(OnExit(STATE), stop_bgm);
+    app.add_systems(OnExit(STATE), (save_unconfirmed_initials, stop_bgm));
+    app.add_systems(
+        Update,
+        (
+            (
+                type_initials,
+                step_initials_with_actions,
+                press_initials_buttons,
+            ),
+            show_high_score_section.run_if(resource_exists_and_changed::<HighScoreEntryState>),
+        )
+            .chain()
+            .run_if(in_state(STATE)),
+    );
 }
 
-fn show_summary_screen(mut commands: Commands, _asset_server: Res<AssetServer>, score: Res<Score>) {
+/// How many letters the player enters.
+const INITIALS_LENGTH: usize = 3;
+
+/// Where the player is with getting onto the high score table.
+#[derive(Resource, Debug)]
+enum HighScoreEntryState {
+    /// The score didn't make the table
+    Missed { best: u32 },
+    /// The score made the table and the player is entering their initials
+    Entering {
+        entry: HighScoreEntry,
+        letters: [u8; INITIALS_LENGTH],
+        cursor: usize,
+    },
+    /// The score has been saved at this rank, starting from 0
+    Saved { rank: usize },
+}
+
+/// Container for the part of the screen that shows the initials entry or the
+/// player's place in the table. It is rebuilt whenever that changes.
+#[derive(Component, Debug)]
+struct HighScoreSection;
+
+/// Button that moves a letter of the initials up or down the alphabet.
+#[derive(Component, Debug)]
+struct LetterStep {
+    slot: usize,
+    step: i8,
+}
+
+/// Button showing a letter of the initials, click it to select it.
+#[derive(Component, Debug)]
+struct LetterSlot(usize);
+
+fn show_summary_screen(
+    mut commands: Commands,
+    _asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    runtime: Res<GameTime>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+    mut rng: ResMut<GameRng>,
+) {
     let restart_game = commands.register_one_shot_system(enter_prep);
     let enter_title = commands.register_one_shot_system(enter_title);
     let score = score.0.floor() as u32;
+    let state = if high_scores.qualifies(*mode, difficulty.level, score) {
+        HighScoreEntryState::Entering {
+            entry: HighScoreEntry {
+                name: String::new(),
+                score,
+                runtime: runtime.time,
+                seed: rng.seed,
+                timestamp: now_unix_seconds(),
+            },
+            letters: [b'A'; INITIALS_LENGTH],
+            cursor: 0,
+        }
+    } else {
+        HighScoreEntryState::Missed {
+            best: high_scores.best(*mode, difficulty.level),
+        }
+    };
+    commands.insert_resource(state);
+
     commands
         .ui_root()
         .insert(StateScoped(STATE))
@@ -23,16 +117,326 @@ fn show_summary_screen(mut commands: Commands, _asset_server: Res<AssetServer>,
 
             // display a sarcastic message if the player gets no points
             if score == 0 {
-                children.label(zero_points_message());
+                children.label(zero_points_message(&mut rng));
+            }
+
+            children.spawn((
+                Name::new("High Score Section"),
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HighScoreSection,
+            ));
+
+            children.shortcut_button(
+                "Again",
+                restart_game,
+                KeyCode::Space,
+                GamepadButtonType::West,
+            );
+            children.action_button("Menu", enter_title, Action::Back);
+        });
+    commands.play_bgm(BgmHandles::TRACK_CREDITS);
+}
+
+/// Fill in the high score section for the current state.
+fn show_high_score_section(
+    mut commands: Commands,
+    state: Res<HighScoreEntryState>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+    sections: Query<Entity, With<HighScoreSection>>,
+) {
+    let save_initials = matches!(*state, HighScoreEntryState::Entering { .. })
+        .then(|| commands.register_one_shot_system(confirm_initials));
+    for section in sections.iter() {
+        let mut section = commands.entity(section);
+        section.despawn_descendants();
+        match &*state {
+            HighScoreEntryState::Missed { best } => {
+                section.with_children(|children| {
+                    children.label(format!("Best on {}: {}", difficulty.level.name(), best));
+                });
+            }
+            HighScoreEntryState::Entering {
+                letters, cursor, ..
+            } => {
+                section.with_children(|children| {
+                    children.label("High score! Enter your initials");
+                    spawn_initials_entry(children, letters, *cursor);
+                    if let Some(save_initials) = save_initials {
+                        children.action_button("Save", save_initials, Action::Confirm);
+                    }
+                });
+            }
+            HighScoreEntryState::Saved { rank } => {
+                let entries = high_scores.entries(*mode, difficulty.level);
+                section.with_children(|children| {
+                    children.label(format!("Rank {} on {}", rank + 1, difficulty.level.name()));
+                    // show the entries either side of the new one
+                    let first = rank.saturating_sub(2);
+                    let last = (rank + 3).min(entries.len());
+                    for (index, entry) in entries[first..last].iter().enumerate() {
+                        let row_rank = first + index;
+                        let mut row = children.label(format!(
+                            "{:>2}. {:<3} {:>7}",
+                            row_rank + 1,
+                            entry.name,
+                            entry.score
+                        ));
+                        if row_rank == *rank {
+                            row.insert(BackgroundColor(NODE_BACKGROUND));
+                        }
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// A row of letter slots, each with buttons to step it up and down.
+fn spawn_initials_entry(children: &mut ChildBuilder, letters: &[u8], cursor: usize) {
+    children
+        .spawn((
+            Name::new("Initials"),
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            // menu up and down change the letters instead
+            BlocksFocusNavigation,
+        ))
+        .with_children(|children| {
+            for (slot, letter) in letters.iter().enumerate() {
+                children
+                    .spawn((
+                        Name::new("Initial"),
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                row_gap: Val::Px(4.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ))
+                    .with_children(|children| {
+                        let selected = slot == cursor;
+                        spawn_letter_button(children, "+", LetterStep { slot, step: 1 }, false);
+                        spawn_letter_button(
+                            children,
+                            char::from(*letter),
+                            LetterSlot(slot),
+                            selected,
+                        );
+                        spawn_letter_button(children, "-", LetterStep { slot, step: -1 }, false);
+                    });
             }
+        });
+}
 
-            children.button("Again", restart_game, Some(KeyCode::Space));
-            children.button("Menu", enter_title, Some(KeyCode::Escape));
+fn spawn_letter_button(
+    children: &mut ChildBuilder,
+    text: impl Into<String>,
+    marker: impl Component,
+    selected: bool,
+) {
+    let background = if selected {
+        BUTTON_PRESSED_BACKGROUND
+    } else {
+        NODE_BACKGROUND
+    };
+    children
+        .spawn((
+            Name::new("Letter Button"),
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(65.0),
+                    height: Val::Px(65.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(background),
+                ..default()
+            },
+            InteractionPalette {
+                none: background,
+                hovered: BUTTON_HOVERED_BACKGROUND,
+                pressed: BUTTON_PRESSED_BACKGROUND,
+            },
+            marker,
+        ))
+        .with_children(|children| {
+            children.spawn((
+                Name::new("Letter Text"),
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font_size: 40.0,
+                        color: ui_palette::BUTTON_TEXT,
+                        ..default()
+                    },
+                ),
+            ));
         });
-    commands.play_bgm(BgmHandles::PATH_CREDITS);
 }
 
-fn zero_points_message() -> &'static str {
+/// Move a letter up or down the alphabet, wrapping from Z back to A.
+fn step_letter(letter: u8, step: i8) -> u8 {
+    let index = (letter - b'A') as i8 + step;
+    b'A' + index.rem_euclid(26) as u8
+}
+
+/// Typing a letter fills in the selected slot and moves on to the next one.
+fn type_initials(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut state: ResMut<HighScoreEntryState>,
+) {
+    let HighScoreEntryState::Entering {
+        letters, cursor, ..
+    } = state.bypass_change_detection()
+    else {
+        keyboard_events.clear();
+        return;
+    };
+    let mut changed = false;
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(text) => {
+                let Some(letter) = text.chars().next().filter(char::is_ascii_alphabetic) else {
+                    continue;
+                };
+                letters[*cursor] = letter.to_ascii_uppercase() as u8;
+                *cursor = (*cursor + 1).min(INITIALS_LENGTH - 1);
+            }
+            Key::Backspace => *cursor = cursor.saturating_sub(1),
+            _ => continue,
+        }
+        changed = true;
+    }
+    if changed {
+        state.set_changed();
+    }
+}
+
+/// Menu up and down step the selected letter, thrust left and right pick a
+/// slot, so the arrow keys, D-Pad and stick all work.
+fn step_initials_with_actions(
+    actions: Res<ButtonInput<Action>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut state: ResMut<HighScoreEntryState>,
+) {
+    // a key that types a letter, like A and D for thrust, fills in a slot instead
+    let typed = keyboard_events.read().any(|event| {
+        event.state == ButtonState::Pressed && matches!(event.logical_key, Key::Character(_))
+    });
+    let HighScoreEntryState::Entering {
+        letters, cursor, ..
+    } = state.bypass_change_detection()
+    else {
+        return;
+    };
+    if typed {
+        return;
+    }
+    let mut changed = true;
+    if actions.just_pressed(Action::MenuUp) {
+        letters[*cursor] = step_letter(letters[*cursor], 1);
+    } else if actions.just_pressed(Action::MenuDown) {
+        letters[*cursor] = step_letter(letters[*cursor], -1);
+    } else if actions.just_pressed(Action::ThrustLeft) {
+        *cursor = (*cursor + INITIALS_LENGTH - 1) % INITIALS_LENGTH;
+    } else if actions.just_pressed(Action::ThrustRight) {
+        *cursor = (*cursor + 1) % INITIALS_LENGTH;
+    } else {
+        changed = false;
+    }
+    if changed {
+        state.set_changed();
+    }
+}
+
+/// Clicking the + and - buttons steps a letter, clicking a letter selects it.
+fn press_initials_buttons(
+    steps: Query<(&Interaction, &LetterStep), Changed<Interaction>>,
+    slots: Query<(&Interaction, &LetterSlot), Changed<Interaction>>,
+    mut state: ResMut<HighScoreEntryState>,
+) {
+    let HighScoreEntryState::Entering {
+        letters, cursor, ..
+    } = state.bypass_change_detection()
+    else {
+        return;
+    };
+    let mut changed = false;
+    for (interaction, step) in steps.iter() {
+        if *interaction == Interaction::Pressed {
+            letters[step.slot] = step_letter(letters[step.slot], step.step);
+            *cursor = step.slot;
+            changed = true;
+        }
+    }
+    for (interaction, slot) in slots.iter() {
+        if *interaction == Interaction::Pressed {
+            *cursor = slot.0;
+            changed = true;
+        }
+    }
+    if changed {
+        state.set_changed();
+    }
+}
+
+/// Save the score with the entered initials.
+fn confirm_initials(
+    mut state: ResMut<HighScoreEntryState>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    let HighScoreEntryState::Entering { entry, letters, .. } = &*state else {
+        return;
+    };
+    let entry = HighScoreEntry {
+        name: String::from_utf8_lossy(letters).into_owned(),
+        ..entry.clone()
+    };
+    *state = match high_scores.insert(*mode, difficulty.level, entry) {
+        Some(rank) => HighScoreEntryState::Saved { rank },
+        None => HighScoreEntryState::Missed {
+            best: high_scores.best(*mode, difficulty.level),
+        },
+    };
+}
+
+/// Leaving the screen without pressing save still keeps the score.
+fn save_unconfirmed_initials(world: &mut World) {
+    if matches!(
+        world.get_resource::<HighScoreEntryState>(),
+        Some(HighScoreEntryState::Entering { .. })
+    ) {
+        world.run_system_once(confirm_initials);
+    }
+    world.remove_resource::<HighScoreEntryState>();
+}
+
+fn zero_points_message(rng: &mut GameRng) -> &'static str {
     // list of sarcastic messages to display when the player gets no points
     let zero_points_message = vec![
         "Wow, you're really good at this game!",
@@ -68,8 +472,7 @@ fn zero_points_message() -> &'static str {
         "Just a little more!",
     ];
     let dist = Uniform::new(0, zero_points_message.len());
-    let mut rng = rand::thread_rng();
-    zero_points_message[rng.sample(dist)]
+    zero_points_message[rng.cosmetic.sample(dist)]
 }
 
 fn stop_bgm(mut commands: Commands) {
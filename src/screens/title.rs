@@ -1,6 +1,6 @@
 //! The title screen that appears when the game starts.
 
-use super::{enter_credits, enter_prep};
+use super::{enter_credits, enter_high_scores, enter_prep, request_options};
 use crate::prelude::*;
 
 const STATE: Screen = Screen::Title;
@@ -12,6 +12,8 @@ pub(super) fn plugin(app: &mut App) {
 
 fn show_title_screen(mut commands: Commands) {
     let enter_prep = commands.register_one_shot_system(enter_prep);
+    let enter_high_scores = commands.register_one_shot_system(enter_high_scores);
+    let request_options = commands.register_one_shot_system(request_options);
     let enter_credits = commands.register_one_shot_system(enter_credits);
     #[cfg(not(target_family = "wasm"))]
     let exit_app = commands.register_one_shot_system(exit_app);
@@ -20,13 +22,32 @@ fn show_title_screen(mut commands: Commands) {
         .ui_root()
         .insert(StateScoped(STATE))
         .with_children(|children| {
-            children.button("Play", enter_prep, Some(KeyCode::KeyP));
-            children.button("Credits", enter_credits, Some(KeyCode::KeyC));
-
+            children.shortcut_button("Play", enter_prep, KeyCode::KeyP, GamepadButtonType::Start);
+            children.shortcut_button(
+                "High Scores",
+                enter_high_scores,
+                KeyCode::KeyH,
+                GamepadButtonType::West,
+            );
+            children.shortcut_button(
+                "Options",
+                request_options,
+                KeyCode::KeyO,
+                GamepadButtonType::Select,
+            );
+            children.shortcut_button(
+                "Credits",
+                enter_credits,
+                KeyCode::KeyC,
+                GamepadButtonType::North,
+            );
+
+            // not on the gamepad's back button, which is pressed too easily to
+            // quit on; focus it and confirm instead
             #[cfg(not(target_family = "wasm"))]
             children.button("Exit", exit_app, Some(KeyCode::Escape));
         });
-    commands.play_bgm(BgmHandles::PATH_TITLES);
+    commands.play_bgm(BgmHandles::TRACK_TITLES);
 }
 
 #[cfg(not(target_family = "wasm"))]
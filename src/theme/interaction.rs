@@ -1,17 +1,30 @@
 use bevy::{ecs::system::SystemId, prelude::*};
 
-use crate::{assets::SfxHandles, audio::sfx::SfxCommands as _};
+use crate::{
+    assets::SfxHandles,
+    audio::sfx::SfxCommands as _,
+    input::{Action, InputBindings},
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<InteractionPalette>();
     app.register_type::<OnPress>();
+    app.init_resource::<ButtonFocus>();
     app.add_systems(
         Update,
         (
             apply_on_press,
-            apply_interaction_palette,
+            (
+                clear_lost_focus,
+                move_focus,
+                activate_focus,
+                apply_action_press,
+                apply_interaction_palette,
+            )
+                .chain(),
             trigger_interaction_sfx,
             apply_keypress,
+            update_action_labels,
         ),
     );
     app.observe(despawn_one_shot_system);
@@ -42,14 +55,67 @@ pub struct OnPress(#[reflect(ignore)] pub SystemId);
 #[derive(Component)]
 pub struct KeyTrigger {
     pub key_code: KeyCode,
+    /// A button on any gamepad that presses it too
+    pub gamepad_button: Option<GamepadButtonType>,
 }
 
 impl KeyTrigger {
     pub fn new(key_code: KeyCode) -> Self {
-        Self { key_code }
+        Self {
+            key_code,
+            gamepad_button: None,
+        }
+    }
+
+    pub fn with_gamepad_button(mut self, button: GamepadButtonType) -> Self {
+        self.gamepad_button = Some(button);
+        self
     }
 }
 
+/// Presses the button when its action is pressed, using whichever keys are
+/// currently bound to it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ActionTrigger(pub Action);
+
+/// Button text that ends with the keys bound to an action, kept up to date as
+/// the bindings change. The text itself is on the button's children.
+#[derive(Component, Debug, Clone)]
+pub struct ActionLabel {
+    pub text: String,
+    pub action: Action,
+}
+
+/// The button picked with the keyboard or gamepad, if any. It is highlighted
+/// like a hovered button and [`Action::Confirm`] presses it.
+#[derive(Resource, Debug, Default)]
+pub struct ButtonFocus(pub Option<Entity>);
+
+/// Stops [`Action::MenuUp`] and [`Action::MenuDown`] moving the focus while it
+/// exists, for UI that uses them for something else. [`Action::MenuNext`]
+/// still moves it.
+#[derive(Component, Debug)]
+pub struct BlocksFocusNavigation;
+
+/// Buttons that can take focus, in the order focus moves through them.
+type FocusableQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static Node,
+        &'static InheritedVisibility,
+    ),
+    With<OnPress>,
+>;
+
+/// Hidden buttons, including ones inside a node that isn't displayed, can't
+/// take focus.
+fn is_focusable(node: &Node, visibility: &InheritedVisibility) -> bool {
+    visibility.get() && node.size() != Vec2::ZERO
+}
+
 fn apply_on_press(
     interaction_query: Query<(&Interaction, &OnPress), Changed<Interaction>>,
     mut commands: Commands,
@@ -66,23 +132,142 @@ fn apply_keypress(
     key_trigger_query: Query<(&KeyTrigger, &OnPress)>,
     mut commands: Commands,
     input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
 ) {
-    // Handle key presses
+    // Handle key and gamepad button presses
     for (key_trigger, &OnPress(system_id)) in &key_trigger_query {
-        if input.just_pressed(key_trigger.key_code) {
+        let pad_pressed = key_trigger.gamepad_button.is_some_and(|button_type| {
+            gamepads
+                .iter()
+                .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button_type)))
+        });
+        if input.just_pressed(key_trigger.key_code) || pad_pressed {
+            commands.run_system(system_id);
+        }
+    }
+}
+
+/// Drop the focus when its button is despawned or hidden.
+fn clear_lost_focus(mut focus: ResMut<ButtonFocus>, buttons: FocusableQuery) {
+    let Some(entity) = focus.0 else {
+        return;
+    };
+    let still_focusable = buttons
+        .get(entity)
+        .is_ok_and(|(_, _, node, visibility)| is_focusable(node, visibility));
+    if !still_focusable {
+        focus.0 = None;
+    }
+}
+
+/// Move the focus through the buttons from top to bottom, wrapping around at
+/// either end. With nothing focused yet, down starts at the top and up at the
+/// bottom.
+fn move_focus(
+    actions: Res<ButtonInput<Action>>,
+    blockers: Query<(), With<BlocksFocusNavigation>>,
+    buttons: FocusableQuery,
+    mut focus: ResMut<ButtonFocus>,
+    mut commands: Commands,
+) {
+    let blocked = !blockers.is_empty();
+    let step: isize = if actions.just_pressed(Action::MenuNext)
+        || (!blocked && actions.just_pressed(Action::MenuDown))
+    {
+        1
+    } else if !blocked && actions.just_pressed(Action::MenuUp) {
+        -1
+    } else {
+        return;
+    };
+
+    let mut order: Vec<_> = buttons
+        .iter()
+        .filter(|(_, _, node, visibility)| is_focusable(node, visibility))
+        .map(|(entity, transform, _, _)| (entity, transform.translation().truncate()))
+        .collect();
+    if order.is_empty() {
+        return;
+    }
+    // UI positions go down the screen, so this reads like a page
+    order.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    let count = order.len() as isize;
+    let current = focus
+        .0
+        .and_then(|entity| order.iter().position(|(other, _)| *other == entity));
+    let next = match current {
+        Some(index) => (index as isize + step).rem_euclid(count),
+        None if step > 0 => 0,
+        None => count - 1,
+    };
+    focus.0 = Some(order[next as usize].0);
+    commands.play_sfx(SfxHandles::KEY_BUTTON_HOVER);
+}
+
+fn activate_focus(
+    actions: Res<ButtonInput<Action>>,
+    focus: Res<ButtonFocus>,
+    buttons: Query<&OnPress>,
+    mut commands: Commands,
+) {
+    if !actions.just_pressed(Action::Confirm) {
+        return;
+    }
+    if let Some(&OnPress(system_id)) = focus.0.and_then(|entity| buttons.get(entity).ok()) {
+        commands.run_system(system_id);
+    }
+}
+
+fn apply_action_press(
+    action_trigger_query: Query<(&ActionTrigger, &OnPress)>,
+    mut commands: Commands,
+    actions: Res<ButtonInput<Action>>,
+    focus: Res<ButtonFocus>,
+) {
+    for (&ActionTrigger(action), &OnPress(system_id)) in &action_trigger_query {
+        // once a button has focus, confirm presses that instead
+        if action == Action::Confirm && focus.0.is_some() {
+            continue;
+        }
+        if actions.just_pressed(action) {
             commands.run_system(system_id);
         }
     }
 }
 
+fn update_action_labels(
+    bindings: Res<InputBindings>,
+    label_query: Query<(Ref<ActionLabel>, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (label, children) in &label_query {
+        if !bindings.is_changed() && !label.is_added() {
+            continue;
+        }
+        let mut texts = text_query.iter_many_mut(children.iter());
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = format!("{} ({})", label.text, bindings.label(label.action));
+        }
+    }
+}
+
 fn apply_interaction_palette(
-    mut palette_query: Query<
-        (&Interaction, &InteractionPalette, &mut BackgroundColor),
-        Changed<Interaction>,
-    >,
+    focus: Res<ButtonFocus>,
+    mut palette_query: Query<(
+        Entity,
+        Ref<Interaction>,
+        &InteractionPalette,
+        &mut BackgroundColor,
+    )>,
 ) {
-    for (interaction, palette, mut background) in &mut palette_query {
-        *background = match interaction {
+    for (entity, interaction, palette, mut background) in &mut palette_query {
+        if !interaction.is_changed() && !focus.is_changed() {
+            continue;
+        }
+        *background = match *interaction {
+            Interaction::None if focus.0 == Some(entity) => palette.hovered,
             Interaction::None => palette.none,
             Interaction::Hovered => palette.hovered,
             Interaction::Pressed => palette.pressed,
@@ -97,8 +282,8 @@ fn trigger_interaction_sfx(
 ) {
     for interaction in &interaction_query {
         match interaction {
-            Interaction::Hovered => commands.play_sfx(SfxHandles::PATH_BUTTON_HOVER),
-            Interaction::Pressed => commands.play_sfx(SfxHandles::PATH_BUTTON_PRESS),
+            Interaction::Hovered => commands.play_sfx(SfxHandles::KEY_BUTTON_HOVER),
+            Interaction::Pressed => commands.play_sfx(SfxHandles::KEY_BUTTON_PRESS),
             _ => (),
         }
     }
@@ -7,9 +7,11 @@ pub mod interaction;
 pub mod palette;
 mod widgets;
 
+pub use widgets::{gamepad_button_name, gamepad_button_to_string, key_name, key_to_string};
+
 pub mod prelude {
     pub use super::{
-        interaction::{InteractionPalette, OnPress},
+        interaction::{BlocksFocusNavigation, InteractionPalette, OnPress},
         palette as ui_palette,
         widgets::{Containers as _, Widgets as _},
     };
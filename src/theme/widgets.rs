@@ -8,9 +8,10 @@ use bevy::{
 };
 
 use super::{
-    interaction::{InteractionPalette, KeyTrigger, OnPress},
+    interaction::{ActionLabel, ActionTrigger, InteractionPalette, KeyTrigger, OnPress},
     palette::*,
 };
+use crate::input::Action;
 
 /// An extension trait for spawning UI widgets.
 pub trait Widgets {
@@ -22,6 +23,25 @@ pub trait Widgets {
         key: Option<KeyCode>,
     ) -> EntityCommands;
 
+    /// Spawn a simple button with text, pressed by either `key` or a gamepad's
+    /// `gamepad_button`.
+    fn shortcut_button(
+        &mut self,
+        text: impl Into<String>,
+        on_press: SystemId,
+        key: KeyCode,
+        gamepad_button: GamepadButtonType,
+    ) -> EntityCommands;
+
+    /// Spawn a simple button with text, pressed by the keys bound to `action`.
+    /// The text shows the bound keys and follows any rebinding.
+    fn action_button(
+        &mut self,
+        text: impl Into<String>,
+        on_press: SystemId,
+        action: Action,
+    ) -> EntityCommands;
+
     /// Spawn a simple header label. Bigger than [`Widgets::label`].
     fn header(&mut self, text: impl Into<String>) -> EntityCommands;
 
@@ -79,6 +99,37 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
+    fn shortcut_button(
+        &mut self,
+        text: impl Into<String>,
+        on_press: SystemId,
+        key: KeyCode,
+        gamepad_button: GamepadButtonType,
+    ) -> EntityCommands {
+        let text = format!(
+            "{} ({} / {})",
+            text.into(),
+            key_to_string(key),
+            gamepad_button_to_string(gamepad_button)
+        );
+        let mut entity = self.button(text, on_press, None);
+        entity.insert(KeyTrigger::new(key).with_gamepad_button(gamepad_button));
+        entity
+    }
+
+    fn action_button(
+        &mut self,
+        text: impl Into<String>,
+        on_press: SystemId,
+        action: Action,
+    ) -> EntityCommands {
+        let text = text.into();
+        let mut entity = self.button(text.clone(), on_press, None);
+        // the bound keys are added to the text once the button is spawned
+        entity.insert((ActionTrigger(action), ActionLabel { text, action }));
+        entity
+    }
+
     fn header(&mut self, text: impl Into<String>) -> EntityCommands {
         let mut entity = self.spawn((
             Name::new("Header"),
@@ -189,8 +240,13 @@ impl Spawn for ChildBuilder<'_> {
 }
 
 /// Converts a [`KeyCode`] to a string representation.
-fn key_to_string(key: KeyCode) -> &'static str {
-    match key {
+pub fn key_to_string(key: KeyCode) -> &'static str {
+    key_name(key).unwrap_or("???")
+}
+
+/// The name shown for a [`KeyCode`], if it has one.
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    let name = match key {
         KeyCode::Digit1 => "1",
         KeyCode::Digit2 => "2",
         KeyCode::Digit3 => "3",
@@ -259,6 +315,65 @@ fn key_to_string(key: KeyCode) -> &'static str {
         KeyCode::ControlLeft | KeyCode::ControlRight => "Ctrl",
         KeyCode::AltLeft | KeyCode::AltRight => "Alt",
         KeyCode::ContextMenu => "Menu",
-        _ => "???",
-    }
+        KeyCode::Minus => "-",
+        KeyCode::Equal => "=",
+        KeyCode::BracketLeft => "[",
+        KeyCode::BracketRight => "]",
+        KeyCode::Backslash => "\\",
+        KeyCode::Semicolon => ";",
+        KeyCode::Quote => "'",
+        KeyCode::Backquote => "`",
+        KeyCode::Comma => ",",
+        KeyCode::Period => ".",
+        KeyCode::Slash => "/",
+        KeyCode::Numpad0 => "Num 0",
+        KeyCode::Numpad1 => "Num 1",
+        KeyCode::Numpad2 => "Num 2",
+        KeyCode::Numpad3 => "Num 3",
+        KeyCode::Numpad4 => "Num 4",
+        KeyCode::Numpad5 => "Num 5",
+        KeyCode::Numpad6 => "Num 6",
+        KeyCode::Numpad7 => "Num 7",
+        KeyCode::Numpad8 => "Num 8",
+        KeyCode::Numpad9 => "Num 9",
+        KeyCode::NumpadAdd => "Num +",
+        KeyCode::NumpadSubtract => "Num -",
+        KeyCode::NumpadMultiply => "Num *",
+        KeyCode::NumpadDivide => "Num /",
+        KeyCode::NumpadDecimal => "Num .",
+        KeyCode::NumpadEnter => "Num Enter",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Converts a [`GamepadButtonType`] to a string representation, named after
+/// the buttons on an Xbox controller.
+pub fn gamepad_button_to_string(button: GamepadButtonType) -> &'static str {
+    gamepad_button_name(button).unwrap_or("???")
+}
+
+/// The name shown for a [`GamepadButtonType`], if it has one.
+pub fn gamepad_button_name(button: GamepadButtonType) -> Option<&'static str> {
+    let name = match button {
+        GamepadButtonType::South => "A",
+        GamepadButtonType::East => "B",
+        GamepadButtonType::West => "X",
+        GamepadButtonType::North => "Y",
+        GamepadButtonType::LeftTrigger => "LB",
+        GamepadButtonType::RightTrigger => "RB",
+        GamepadButtonType::LeftTrigger2 => "LT",
+        GamepadButtonType::RightTrigger2 => "RT",
+        GamepadButtonType::Select => "Select",
+        GamepadButtonType::Start => "Start",
+        GamepadButtonType::Mode => "Home",
+        GamepadButtonType::LeftThumb => "LS",
+        GamepadButtonType::RightThumb => "RS",
+        GamepadButtonType::DPadUp => "D-Pad ↑",
+        GamepadButtonType::DPadDown => "D-Pad ↓",
+        GamepadButtonType::DPadLeft => "D-Pad ←",
+        GamepadButtonType::DPadRight => "D-Pad →",
+        _ => return None,
+    };
+    Some(name)
 }